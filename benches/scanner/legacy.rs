@@ -0,0 +1,545 @@
+//! The scanner as it was before it started walking `&str` directly: the
+//! program is copied into a `Vec<char>` and every token owns its lexeme.
+//! Kept here as the baseline the zero-copy scanner is measured against.
+
+use rslox::span::{FileId, Span};
+use rslox::token::{TokenType, Value};
+use unicode_xid::UnicodeXID;
+
+// The benchmark only counts tokens, their contents are never read
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: String,
+    pub value: Option<Value>,
+    pub span: Span,
+}
+
+impl Token {
+    fn new(token_type: TokenType, lexeme: String, span: Span) -> Token {
+        Token {
+            token_type,
+            lexeme,
+            value: None,
+            span,
+        }
+    }
+
+    fn new_string(lexeme: String, string: String, span: Span) -> Token {
+        Token {
+            token_type: TokenType::String,
+            lexeme,
+            value: Some(Value::String(string)),
+            span,
+        }
+    }
+
+    fn new_number(lexeme: String, number: f64, span: Span) -> Token {
+        Token {
+            token_type: TokenType::Number,
+            lexeme,
+            value: Some(Value::Number(number)),
+            span,
+        }
+    }
+
+    fn new_doc_comment(lexeme: String, span: Span) -> Token {
+        let text = lexeme.trim_start_matches('/');
+        let text = text.strip_prefix(' ').unwrap_or(text).to_string();
+        Token {
+            token_type: TokenType::DocComment,
+            lexeme,
+            value: Some(Value::String(text)),
+            span,
+        }
+    }
+
+    fn new_identifier(identifier: String, span: Span) -> Token {
+        let token_type = match identifier.as_str() {
+            "and" => TokenType::And,
+            "class" => TokenType::Class,
+            "else" => TokenType::Else,
+            "false" => TokenType::False,
+            "fun" => TokenType::Fun,
+            "for" => TokenType::For,
+            "if" => TokenType::If,
+            "nil" => TokenType::Nil,
+            "or" => TokenType::Or,
+            "print" => TokenType::Print,
+            "return" => TokenType::Return,
+            "super" => TokenType::Super,
+            "this" => TokenType::This,
+            "true" => TokenType::True,
+            "var" => TokenType::Var,
+            "while" => TokenType::While,
+            _ => TokenType::Identifier,
+        };
+        Token {
+            token_type,
+            lexeme: identifier,
+            value: None,
+            span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScanErrorKind {
+    UnexpectedCharacter,
+    UnterminatedString,
+    InvalidEscape,
+    MalformedNumber,
+    UnterminatedComment,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+impl ScanError {
+    pub fn new(kind: ScanErrorKind, line: usize, column: usize, text: String) -> ScanError {
+        ScanError {
+            kind,
+            line,
+            column,
+            text,
+        }
+    }
+}
+
+struct Scanner {
+    code: Vec<char>,
+    file: FileId,
+    tokens: Vec<Token>,
+    errors: Vec<ScanError>,
+    current: usize,
+    current_char: char,
+    line: usize,
+    column: usize,
+    // Byte offset of the next character
+    offset: usize,
+    // Where the token being scanned starts
+    start_offset: usize,
+    start_line: usize,
+    start_column: usize,
+}
+
+impl Scanner {
+    fn new(program: &str, file: FileId) -> Scanner {
+        Scanner {
+            code: program.chars().collect::<Vec<char>>(),
+            file,
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            current: 0,
+            current_char: '\0',
+            line: 1,
+            column: 0,
+            offset: 0,
+            start_offset: 0,
+            start_line: 1,
+            start_column: 1,
+        }
+    }
+
+    fn peek(&self) -> char {
+        if self.current >= self.code.len() {
+            '\0'
+        } else {
+            self.code[self.current]
+        }
+    }
+
+    fn peek_next(&self) -> char {
+        if self.current + 1 >= self.code.len() {
+            '\0'
+        } else {
+            self.code[self.current + 1]
+        }
+    }
+
+    fn next(&mut self) -> char {
+        let nc = self.peek();
+        self.current += 1;
+        self.current_char = nc;
+        self.offset += nc.len_utf8();
+        if nc == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        nc
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.code.len()
+    }
+
+    // Span from the start of the current token up to the last consumed char
+    fn current_span(&self) -> Span {
+        Span::new(
+            self.file,
+            self.start_offset,
+            self.offset,
+            self.start_line,
+            self.start_column,
+        )
+    }
+
+    fn add_token(&mut self, token_type: TokenType, lexeme: String) {
+        self.tokens
+            .push(Token::new(token_type, lexeme, self.current_span()));
+    }
+
+    fn add_error(&mut self, kind: ScanErrorKind, text: String) {
+        self.add_error_at(kind, text, self.line, self.column);
+    }
+
+    fn add_error_at(&mut self, kind: ScanErrorKind, text: String, line: usize, column: usize) {
+        self.errors.push(ScanError::new(kind, line, column, text));
+    }
+
+    fn scan(&mut self) -> (Vec<Token>, Vec<ScanError>) {
+        while !self.is_at_end() {
+            self.scan_token();
+        }
+        (self.tokens.clone(), self.errors.clone())
+    }
+
+    fn scan_token(&mut self) {
+        self.start_offset = self.offset;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+        let c = self.next().to_string();
+        match c.as_str() {
+            c if c.trim().is_empty() => (),
+            "(" => self.add_token(TokenType::LeftParen, c),
+            ")" => self.add_token(TokenType::RightParen, c),
+            "{" => self.add_token(TokenType::LeftBrace, c),
+            "}" => self.add_token(TokenType::RightBrace, c),
+            "," => self.add_token(TokenType::Comma, c),
+            "." => self.add_token(TokenType::Dot, c),
+            "-" => self.add_token(TokenType::Minus, c),
+            "+" => self.add_token(TokenType::Plus, c),
+            ";" => self.add_token(TokenType::Semicolon, c),
+            "*" => self.add_token(TokenType::Star, c),
+            "!" => {
+                if self.peek() == '=' {
+                    self.next();
+                    self.add_token(TokenType::BangEquals, "!=".to_string());
+                } else {
+                    self.add_token(TokenType::Bang, c);
+                }
+            }
+            "=" => {
+                if self.peek() == '=' {
+                    self.next();
+                    self.add_token(TokenType::EqualsEquals, "==".to_string());
+                } else {
+                    self.add_token(TokenType::Equals, c);
+                }
+            }
+            "<" => {
+                if self.peek() == '=' {
+                    self.next();
+                    self.add_token(TokenType::LessEqual, "<=".to_string());
+                } else {
+                    self.add_token(TokenType::Less, c);
+                }
+            }
+            ">" => {
+                if self.peek() == '=' {
+                    self.next();
+                    self.add_token(TokenType::GreaterEqual, ">=".to_string());
+                } else {
+                    self.add_token(TokenType::Greater, c);
+                }
+            }
+            "/" => {
+                if self.peek() == '/' {
+                    self.scan_line_comment();
+                } else if self.peek() == '*' {
+                    self.scan_block_comment();
+                } else {
+                    self.add_token(TokenType::Slash, c);
+                }
+            }
+            "\"" => self.scan_string(),
+            "r" if self.peek() == '"' => {
+                self.next();
+                self.scan_raw_string();
+            }
+            c if is_identifier_start(c) => self.scan_identifier(),
+            c if is_numeric(c) => self.scan_number(),
+            _ => self.add_error(ScanErrorKind::UnexpectedCharacter, c),
+        };
+    }
+
+    // Comment until the end of the line. Exactly three slashes make it a doc
+    // comment, which is kept as a token for the declaration that follows.
+    fn scan_line_comment(&mut self) {
+        let mut lexeme = String::from("/");
+        while self.peek() != '\n' && !self.is_at_end() {
+            lexeme.push(self.next());
+        }
+        if lexeme.starts_with("///") && !lexeme.starts_with("////") {
+            self.tokens
+                .push(Token::new_doc_comment(lexeme, self.current_span()));
+        }
+    }
+
+    // Block comments nest, so /* a /* b */ c */ is a single comment
+    fn scan_block_comment(&mut self) {
+        self.next();
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                self.add_error_at(
+                    ScanErrorKind::UnterminatedComment,
+                    String::from("/*"),
+                    self.start_line,
+                    self.start_column,
+                );
+                return;
+            }
+            let c = self.next();
+            if c == '/' && self.peek() == '*' {
+                self.next();
+                depth += 1;
+            } else if c == '*' && self.peek() == '/' {
+                self.next();
+                depth -= 1;
+            }
+        }
+    }
+
+    // Strings can span several lines and contain any character. The lexeme
+    // is the text between the quotes, the value has its escapes resolved.
+    fn scan_string(&mut self) {
+        let mut lexeme = String::new();
+        let mut value = String::new();
+        loop {
+            if self.is_at_end() {
+                self.add_unterminated_string_error(lexeme);
+                return;
+            }
+            let c = self.next();
+            if c == '"' {
+                break;
+            }
+            lexeme.push(c);
+            if c == '\\' {
+                self.scan_escape(&mut lexeme, &mut value);
+            } else {
+                value.push(c);
+            }
+        }
+        self.tokens
+            .push(Token::new_string(lexeme, value, self.current_span()));
+    }
+
+    // Raw strings (r"...") take their contents verbatim, without escapes
+    fn scan_raw_string(&mut self) {
+        let mut lexeme = String::new();
+        loop {
+            if self.is_at_end() {
+                self.add_unterminated_string_error(lexeme);
+                return;
+            }
+            let c = self.next();
+            if c == '"' {
+                break;
+            }
+            lexeme.push(c);
+        }
+        self.tokens
+            .push(Token::new_string(lexeme.clone(), lexeme, self.current_span()));
+    }
+
+    fn add_unterminated_string_error(&mut self, lexeme: String) {
+        let text = format!("\"{}", lexeme);
+        self.add_error_at(
+            ScanErrorKind::UnterminatedString,
+            text,
+            self.start_line,
+            self.start_column,
+        );
+    }
+
+    // Called right after a backslash was consumed inside a string
+    fn scan_escape(&mut self, lexeme: &mut String, value: &mut String) {
+        let (line, column) = (self.line, self.column);
+        let escape_start = lexeme.len() - 1;
+        if self.is_at_end() {
+            return;
+        }
+        let c = self.next();
+        lexeme.push(c);
+        let escaped = match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            'u' => self.scan_unicode_escape(lexeme),
+            _ => None,
+        };
+        match escaped {
+            Some(escaped) => value.push(escaped),
+            None => {
+                let text = lexeme[escape_start..].to_string();
+                self.add_error_at(ScanErrorKind::InvalidEscape, text, line, column);
+            }
+        }
+    }
+
+    // \u{XXXX} with one to six hex digits naming a unicode scalar value
+    fn scan_unicode_escape(&mut self, lexeme: &mut String) -> Option<char> {
+        if self.peek() != '{' {
+            return None;
+        }
+        lexeme.push(self.next());
+        let mut digits = String::new();
+        while self.peek().is_ascii_hexdigit() {
+            digits.push(self.next());
+        }
+        lexeme.push_str(&digits);
+        if self.peek() != '}' {
+            return None;
+        }
+        lexeme.push(self.next());
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    // Identifiers follow Unicode's XID_Start/XID_Continue rules, plus `_`
+    fn scan_identifier(&mut self) {
+        let mut id = String::from(self.current_char);
+        while is_identifier_char(self.peek()) {
+            id.push(self.next());
+        }
+        self.tokens
+            .push(Token::new_identifier(id, self.current_span()));
+    }
+
+    // Numbers are decimal (1_000, 3.14, 2.5e-3), hexadecimal (0xff) or
+    // binary (0b1010). A `_` is only allowed between two digits.
+    fn scan_number(&mut self) {
+        let mut lexeme = String::from(self.current_char);
+        let number = match (self.current_char, self.peek()) {
+            ('0', 'x' | 'X') => self.scan_radix_number(&mut lexeme, 16),
+            ('0', 'b' | 'B') => self.scan_radix_number(&mut lexeme, 2),
+            _ => self.scan_decimal_number(&mut lexeme),
+        };
+
+        // Anything glued to the number makes the whole word malformed
+        let mut malformed = number.is_none();
+        while is_identifier_char(self.peek()) {
+            lexeme.push(self.next());
+            malformed = true;
+        }
+
+        match number {
+            Some(number) if !malformed => {
+                self.tokens
+                    .push(Token::new_number(lexeme, number, self.current_span()));
+            }
+            _ => self.add_error_at(
+                ScanErrorKind::MalformedNumber,
+                lexeme,
+                self.start_line,
+                self.start_column,
+            ),
+        }
+    }
+
+    fn scan_decimal_number(&mut self, lexeme: &mut String) -> Option<f64> {
+        self.scan_digits(lexeme, 10);
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            lexeme.push(self.next());
+            self.scan_digits(lexeme, 10);
+        }
+        if matches!(self.peek(), 'e' | 'E') {
+            let sign = matches!(self.peek_next(), '+' | '-');
+            let digit = if sign {
+                self.code.get(self.current + 2).copied().unwrap_or('\0')
+            } else {
+                self.peek_next()
+            };
+            if digit.is_ascii_digit() {
+                lexeme.push(self.next());
+                if sign {
+                    lexeme.push(self.next());
+                }
+                self.scan_digits(lexeme, 10);
+            }
+        }
+
+        if !has_valid_separators(lexeme, 10) {
+            return None;
+        }
+        lexeme.replace('_', "").parse::<f64>().ok()
+    }
+
+    // The leading 0 is already in `lexeme`, the radix marker is next
+    fn scan_radix_number(&mut self, lexeme: &mut String, radix: u32) -> Option<f64> {
+        lexeme.push(self.next());
+        let digits_start = lexeme.len();
+        self.scan_digits(lexeme, radix);
+
+        let digits = &lexeme[digits_start..];
+        if digits.is_empty() || !has_valid_separators(digits, radix) {
+            return None;
+        }
+        u64::from_str_radix(&digits.replace('_', ""), radix)
+            .ok()
+            .map(|number| number as f64)
+    }
+
+    fn scan_digits(&mut self, lexeme: &mut String, radix: u32) {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            lexeme.push(self.next());
+        }
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_xid_continue()
+}
+
+// Every `_` in a number must sit between two digits
+fn has_valid_separators(literal: &str, radix: u32) -> bool {
+    let chars: Vec<char> = literal.chars().collect();
+    chars.iter().enumerate().all(|(i, c)| {
+        *c != '_'
+            || (i > 0
+                && chars[i - 1].is_digit(radix)
+                && i + 1 < chars.len()
+                && chars[i + 1].is_digit(radix))
+    })
+}
+
+fn is_identifier_start(c: &str) -> bool {
+    let c = c.chars().next().unwrap();
+    c.is_xid_start() || c == '_'
+}
+
+fn is_numeric(c: &str) -> bool {
+    c.chars().next().unwrap().is_ascii_digit()
+}
+
+pub fn scan_program(program: &str) -> (Vec<Token>, Vec<ScanError>) {
+    let mut scanner = Scanner::new(program, FileId::default());
+    scanner.scan()
+}
@@ -0,0 +1,82 @@
+//! Scanner throughput, zero-copy `Scanner` against the previous `Vec<char>`
+//! based implementation. Run with `cargo bench --bench scanner`.
+
+mod legacy;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rslox::scanner::{scan_program, Scanner};
+use rslox::span::FileId;
+
+const SNIPPET: &str = r#"
+/// Computes the n-th fibonacci number.
+fun fibonacci(n) {
+    if (n <= 1) return n; // base case
+    return fibonacci(n - 2) + fibonacci(n - 1);
+}
+
+class Greeter {
+    init(name) {
+        this.name = name;
+    }
+
+    greet() {
+        print "Hello, " + this.name + "! \u{1F44B}";
+    }
+}
+
+/* generated block
+   of statements */
+var total = 0;
+for (var i = 0; i < 1_000; i = i + 1) {
+    total = total + i * 2.5e-1 - 0x10 / 0b11;
+}
+"#;
+
+const REPETITIONS: usize = 2_000;
+const ROUNDS: u32 = 10;
+
+fn measure(name: &str, program: &str, mut scan: impl FnMut(&str) -> usize) {
+    // Warm up once so allocation and caches settle
+    black_box(scan(program));
+
+    let mut elapsed = Duration::ZERO;
+    let mut tokens = 0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        tokens = black_box(scan(black_box(program)));
+        elapsed += start.elapsed();
+    }
+
+    let per_round = elapsed / ROUNDS;
+    let megabytes = program.len() as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:<22} {:>8} tokens  {:>10.2?}/round  {:>8.2} MiB/s",
+        name,
+        tokens,
+        per_round,
+        megabytes / per_round.as_secs_f64()
+    );
+}
+
+fn main() {
+    let program = SNIPPET.repeat(REPETITIONS);
+    println!(
+        "scanning {:.2} MiB, {} rounds each",
+        program.len() as f64 / (1024.0 * 1024.0),
+        ROUNDS
+    );
+
+    measure("legacy scan_program", &program, |program| {
+        legacy::scan_program(program).0.len()
+    });
+    measure("scan_program", &program, |program| {
+        scan_program(program).0.len()
+    });
+    measure("Scanner iterator", &program, |program| {
+        Scanner::new(program, FileId::default())
+            .filter(Result::is_ok)
+            .count()
+    });
+}
@@ -0,0 +1,200 @@
+//! Concrete syntax tree: a lossless view of the source for tools that
+//! rewrite code without destroying its formatting. Every byte of the source,
+//! including whitespace, comments and text that failed to scan, ends up in
+//! the tree, so printing it gives back the exact input.
+
+use std::fmt;
+
+use crate::scanner::{Piece, ScanError, Scanner};
+use crate::span::FileId;
+use crate::token::{Token, TokenType, Trivia, TriviaKind};
+
+/// A token together with the trivia around it. Trailing trivia runs up to,
+/// but not including, the end of the token's line; everything after that is
+/// leading trivia of the next token.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TriviaToken<'a> {
+    pub leading: Vec<Trivia<'a>>,
+    pub token: Token<'a>,
+    // The token's source text, which for strings includes the quotes the
+    // lexeme leaves out
+    pub text: &'a str,
+    pub trailing: Vec<Trivia<'a>>,
+}
+
+impl<'a> TriviaToken<'a> {
+    pub fn new(leading: Vec<Trivia<'a>>, token: Token<'a>, text: &'a str) -> TriviaToken<'a> {
+        TriviaToken {
+            leading,
+            token,
+            text,
+            trailing: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for TriviaToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(trivia.text)?;
+        }
+        f.write_str(self.text)?;
+        for trivia in &self.trailing {
+            f.write_str(trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyntaxKind {
+    Program,
+    // ( ... )
+    Group,
+    // { ... }
+    Block,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxElement<'a> {
+    Token(TriviaToken<'a>),
+    Node(SyntaxNode<'a>),
+}
+
+impl fmt::Display for SyntaxElement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyntaxElement::Token(token) => token.fmt(f),
+            SyntaxElement::Node(node) => node.fmt(f),
+        }
+    }
+}
+
+/// A node of the tree. Groups and blocks start with their opening delimiter
+/// and end with the closing one, when the source has it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxNode<'a> {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+    pub fn new(kind: SyntaxKind) -> SyntaxNode<'a> {
+        SyntaxNode {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// Every token below this node, in source order.
+    pub fn tokens(&self) -> Vec<&TriviaToken<'a>> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Token(token) => tokens.push(token),
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+            }
+        }
+        tokens
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans `source` keeping its trivia. The last token is always `Eof`, which
+/// holds whatever trivia follows the last real token.
+pub fn scan_with_trivia(file: FileId, source: &str) -> (Vec<TriviaToken<'_>>, Vec<ScanError>) {
+    let mut scanner = Scanner::new(source, file);
+    let mut tokens: Vec<TriviaToken> = Vec::new();
+    let mut errors = Vec::new();
+    let mut pending = Vec::new();
+    // Whether trivia still belongs to the line of the previous token
+    let mut trailing = false;
+
+    while let Some(piece) = scanner.next_piece() {
+        match piece {
+            Piece::Token(token) => {
+                let text = &source[token.span.start..token.span.end];
+                tokens.push(TriviaToken::new(std::mem::take(&mut pending), token, text));
+                trailing = true;
+            }
+            Piece::Trivia(trivia) => {
+                if trivia.kind == TriviaKind::Newline {
+                    trailing = false;
+                }
+                match tokens.last_mut() {
+                    Some(previous) if trailing => previous.trailing.push(trivia),
+                    _ => pending.push(trivia),
+                }
+            }
+            Piece::Error(error) => errors.push(error),
+        }
+    }
+    (tokens, errors)
+}
+
+/// Builds the concrete syntax tree of `source`. Parentheses and braces
+/// become nested nodes; a closing delimiter without a matching opening one
+/// stays a plain token, and unclosed nodes end at the end of the file.
+pub fn parse_cst(file: FileId, source: &str) -> (SyntaxNode<'_>, Vec<ScanError>) {
+    let (tokens, errors) = scan_with_trivia(file, source);
+    let mut stack = vec![SyntaxNode::new(SyntaxKind::Program)];
+
+    for token in tokens {
+        match token.token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace => {
+                let kind = if token.token.token_type == TokenType::LeftParen {
+                    SyntaxKind::Group
+                } else {
+                    SyntaxKind::Block
+                };
+                let mut node = SyntaxNode::new(kind);
+                node.children.push(SyntaxElement::Token(token));
+                stack.push(node);
+            }
+            TokenType::RightParen | TokenType::RightBrace
+                if closes(stack.last().unwrap().kind, token.token.token_type) =>
+            {
+                let mut node = stack.pop().unwrap();
+                node.children.push(SyntaxElement::Token(token));
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .children
+                    .push(SyntaxElement::Node(node));
+            }
+            TokenType::Eof => {
+                // Close whatever is still open so Eof lands in the root
+                while stack.len() > 1 {
+                    let node = stack.pop().unwrap();
+                    stack
+                        .last_mut()
+                        .unwrap()
+                        .children
+                        .push(SyntaxElement::Node(node));
+                }
+                stack[0].children.push(SyntaxElement::Token(token));
+            }
+            _ => stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(SyntaxElement::Token(token)),
+        }
+    }
+    (stack.pop().unwrap(), errors)
+}
+
+fn closes(kind: SyntaxKind, token_type: TokenType) -> bool {
+    matches!(
+        (kind, token_type),
+        (SyntaxKind::Group, TokenType::RightParen) | (SyntaxKind::Block, TokenType::RightBrace)
+    )
+}
@@ -0,0 +1,132 @@
+use crate::cst::{parse_cst, scan_with_trivia, SyntaxElement, SyntaxKind};
+use crate::span::FileId;
+use crate::token::{TokenType, TriviaKind};
+
+fn round_trip(source: &str) {
+    let (tree, _) = parse_cst(FileId::default(), source);
+    assert_eq!(source, tree.to_string());
+}
+
+#[test]
+fn empty_program_round_trips() {
+    round_trip("");
+}
+
+#[test]
+fn program_with_comments_round_trips() {
+    round_trip(
+        r#"
+/// Greets someone.
+fun greet(name) {
+    // say hello
+    print "Hello, " + name;   /* trailing /* nested */ */
+}
+
+  greet( "wörld" ) ;
+"#,
+    );
+}
+
+#[test]
+fn crlf_tabs_and_unicode_round_trip() {
+    round_trip("var café\t=\r\n  r\"raw\\text\" ;\r\n// done");
+}
+
+#[test]
+fn interpolated_strings_round_trip() {
+    round_trip(r#"print "a ${ b + "c ${d}" } e";"#);
+}
+
+#[test]
+fn source_with_errors_round_trips() {
+    round_trip("var @ x = 12abc + \"ok\\q\" # ;");
+    round_trip("print 1; /* never closed");
+    round_trip("print \"never closed\n  at all");
+    round_trip(") } ( { [");
+}
+
+#[test]
+fn skipped_text_is_trivia() {
+    let (tokens, errors) = scan_with_trivia(FileId::default(), "a @ b");
+    assert_eq!(1, errors.len());
+    let trailing: Vec<(TriviaKind, &str)> = tokens[0]
+        .trailing
+        .iter()
+        .map(|trivia| (trivia.kind, trivia.text))
+        .collect();
+    assert_eq!(
+        [
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::Skipped, "@"),
+            (TriviaKind::Whitespace, " ")
+        ],
+        &trailing[..]
+    );
+}
+
+#[test]
+fn trivia_on_the_same_line_trails_and_the_rest_leads() {
+    let (tokens, _) = scan_with_trivia(FileId::default(), "a; // end\n\n  /* doc */ b\n");
+    let semicolon = &tokens[1];
+    assert_eq!(TokenType::Semicolon, semicolon.token.token_type);
+    let trailing: Vec<&str> = semicolon.trailing.iter().map(|trivia| trivia.text).collect();
+    assert_eq!([" ", "// end"], &trailing[..]);
+
+    let b = &tokens[2];
+    let leading: Vec<TriviaKind> = b.leading.iter().map(|trivia| trivia.kind).collect();
+    assert_eq!(
+        [
+            TriviaKind::Newline,
+            TriviaKind::Newline,
+            TriviaKind::Whitespace,
+            TriviaKind::BlockComment,
+            TriviaKind::Whitespace
+        ],
+        &leading[..]
+    );
+
+    let eof = &tokens[3];
+    assert_eq!(TokenType::Eof, eof.token.token_type);
+    assert_eq!(1, eof.leading.len());
+    assert_eq!(TriviaKind::Newline, eof.leading[0].kind);
+}
+
+#[test]
+fn string_tokens_keep_their_quotes() {
+    let (tokens, _) = scan_with_trivia(FileId::default(), r#""a\tb""#);
+    assert_eq!(r#"a\tb"#, tokens[0].token.lexeme);
+    assert_eq!(r#""a\tb""#, tokens[0].text);
+}
+
+#[test]
+fn delimiters_become_nested_nodes() {
+    let (tree, _) = parse_cst(FileId::default(), "f(a, (b)) { c; }");
+    assert_eq!(SyntaxKind::Program, tree.kind);
+    let kinds: Vec<Option<SyntaxKind>> = tree
+        .children
+        .iter()
+        .map(|child| match child {
+            SyntaxElement::Token(_) => None,
+            SyntaxElement::Node(node) => Some(node.kind),
+        })
+        .collect();
+    assert_eq!(
+        [None, Some(SyntaxKind::Group), Some(SyntaxKind::Block), None],
+        &kinds[..]
+    );
+    match &tree.children[1] {
+        SyntaxElement::Node(group) => {
+            assert_eq!("(a, (b)) ", group.to_string());
+            assert_eq!(7, group.tokens().len());
+        }
+        SyntaxElement::Token(_) => panic!("expected a group"),
+    }
+}
+
+#[test]
+fn unclosed_delimiters_end_at_eof() {
+    let (tree, _) = parse_cst(FileId::default(), "{ (a");
+    assert_eq!(2, tree.children.len());
+    let last = tree.tokens().pop().unwrap();
+    assert_eq!(TokenType::Eof, last.token.token_type);
+}
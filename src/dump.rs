@@ -0,0 +1,140 @@
+//! Printable listings of scanned tokens, for `rslox tokens`. The text format
+//! matches the output of the craftinginterpreters reference implementation,
+//! `TYPE lexeme literal` per line; the JSON format adds positions for scripts.
+
+use std::fmt::Write;
+
+use crate::token::{Token, TokenType, Value};
+use crate::value::format_number;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DumpFormat {
+    Text,
+    Json,
+}
+
+/// Lists `tokens`, which were scanned from `source`, one per line.
+pub fn dump_tokens(source: &str, tokens: &[Token], format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Text => dump_text(source, tokens),
+        DumpFormat::Json => dump_json(source, tokens),
+    }
+}
+
+fn dump_text(source: &str, tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        let literal = match &token.value {
+            Some(Value::String(string)) => string.clone(),
+            Some(Value::Number(number)) => format_number(*number),
+            None => String::from("null"),
+        };
+        writeln!(
+            out,
+            "{} {} {}",
+            type_name(token.token_type),
+            text(source, token),
+            literal
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn dump_json(source: &str, tokens: &[Token]) -> String {
+    let mut out = String::from("[\n");
+    for (index, token) in tokens.iter().enumerate() {
+        let literal = match &token.value {
+            Some(Value::String(string)) => json_string(string),
+            // JSON has no NaN or infinities
+            Some(Value::Number(number)) if !number.is_finite() => String::from("null"),
+            Some(Value::Number(number)) => format_number(*number),
+            None => String::from("null"),
+        };
+        write!(
+            out,
+            "  {{\"type\": \"{}\", \"lexeme\": {}, \"literal\": {}, \"line\": {}, \"column\": {}, \"start\": {}, \"end\": {}}}",
+            type_name(token.token_type),
+            json_string(text(source, token)),
+            literal,
+            token.span.line,
+            token.span.column,
+            token.span.start,
+            token.span.end
+        )
+        .unwrap();
+        out.push_str(if index + 1 < tokens.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+// The token as written in the source, which for strings includes the
+// quotes the lexeme leaves out
+fn text<'s>(source: &'s str, token: &Token) -> &'s str {
+    &source[token.span.start..token.span.end]
+}
+
+fn json_string(string: &str) -> String {
+    let mut out = String::with_capacity(string.len() + 2);
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn type_name(token_type: TokenType) -> &'static str {
+    match token_type {
+        TokenType::LeftParen => "LEFT_PAREN",
+        TokenType::RightParen => "RIGHT_PAREN",
+        TokenType::LeftBrace => "LEFT_BRACE",
+        TokenType::RightBrace => "RIGHT_BRACE",
+        TokenType::Comma => "COMMA",
+        TokenType::Dot => "DOT",
+        TokenType::Minus => "MINUS",
+        TokenType::Plus => "PLUS",
+        TokenType::Semicolon => "SEMICOLON",
+        TokenType::Slash => "SLASH",
+        TokenType::Star => "STAR",
+        TokenType::Equals => "EQUAL",
+        TokenType::EqualsEquals => "EQUAL_EQUAL",
+        TokenType::Bang => "BANG",
+        TokenType::BangEquals => "BANG_EQUAL",
+        TokenType::Greater => "GREATER",
+        TokenType::GreaterEqual => "GREATER_EQUAL",
+        TokenType::Less => "LESS",
+        TokenType::LessEqual => "LESS_EQUAL",
+        TokenType::Identifier => "IDENTIFIER",
+        TokenType::String => "STRING",
+        TokenType::Number => "NUMBER",
+        TokenType::Interpolation => "INTERPOLATION",
+        TokenType::DocComment => "DOC_COMMENT",
+        TokenType::And => "AND",
+        TokenType::Class => "CLASS",
+        TokenType::Else => "ELSE",
+        TokenType::False => "FALSE",
+        TokenType::Fun => "FUN",
+        TokenType::For => "FOR",
+        TokenType::If => "IF",
+        TokenType::Nil => "NIL",
+        TokenType::Or => "OR",
+        TokenType::Print => "PRINT",
+        TokenType::Return => "RETURN",
+        TokenType::Super => "SUPER",
+        TokenType::This => "THIS",
+        TokenType::True => "TRUE",
+        TokenType::Var => "VAR",
+        TokenType::While => "WHILE",
+        TokenType::Eof => "EOF",
+    }
+}
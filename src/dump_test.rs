@@ -0,0 +1,82 @@
+use crate::dump::{dump_tokens, DumpFormat};
+use crate::scanner::scan_program;
+use crate::span::{FileId, Span};
+use crate::token::Token;
+
+fn dump(source: &str, format: DumpFormat) -> String {
+    let (tokens, errors) = scan_program(source);
+    assert!(errors.is_empty());
+    dump_tokens(source, &tokens, format)
+}
+
+#[test]
+fn text_matches_the_reference_format() {
+    let source = "var pi = 3.5;\nprint \"pi\" + 12 != nil;";
+    let expected = "\
+VAR var null
+IDENTIFIER pi null
+EQUAL = null
+NUMBER 3.5 3.5
+SEMICOLON ; null
+PRINT print null
+STRING \"pi\" pi
+PLUS + null
+NUMBER 12 12.0
+BANG_EQUAL != null
+NIL nil null
+SEMICOLON ; null
+EOF  null
+";
+    assert_eq!(expected, dump(source, DumpFormat::Text));
+}
+
+#[test]
+fn json_has_positions_and_escapes() {
+    let source = "x\n  \"a\\tb\"";
+    let expected = r#"[
+  {"type": "IDENTIFIER", "lexeme": "x", "literal": null, "line": 1, "column": 1, "start": 0, "end": 1},
+  {"type": "STRING", "lexeme": "\"a\\tb\"", "literal": "a\tb", "line": 2, "column": 3, "start": 4, "end": 10},
+  {"type": "EOF", "lexeme": "", "literal": null, "line": 2, "column": 9, "start": 10, "end": 10}
+]
+"#;
+    assert_eq!(expected, dump(source, DumpFormat::Json));
+}
+
+#[test]
+fn empty_program_dumps_eof() {
+    assert_eq!("EOF  null\n", dump("", DumpFormat::Text));
+    assert_eq!(
+        "[\n  {\"type\": \"EOF\", \"lexeme\": \"\", \"literal\": null, \"line\": 1, \"column\": 1, \"start\": 0, \"end\": 0}\n]\n",
+        dump("", DumpFormat::Json)
+    );
+}
+
+#[test]
+fn numbers_print_as_java_doubles() {
+    let expected = "\
+NUMBER 10000000 1.0E7
+NUMBER 0.0001 1.0E-4
+NUMBER 1.2345678901234567e19 1.2345678901234567E19
+NUMBER 0.001 0.001
+NUMBER 1234567 1234567.0
+EOF  null
+";
+    assert_eq!(
+        expected,
+        dump("10000000 0.0001 1.2345678901234567e19 0.001 1234567", DumpFormat::Text)
+    );
+}
+
+#[test]
+fn json_numbers_are_valid_json() {
+    let source = "1e7 x";
+    let (mut tokens, _) = scan_program(source);
+    assert!(dump(source, DumpFormat::Json).contains("\"literal\": 1.0E7,"));
+
+    // The scanner rejects literals too big for an f64, but tokens can come
+    // from elsewhere
+    let span = Span::new(FileId::default(), 4, 5, 1, 5);
+    tokens[1] = Token::new_number("x", f64::INFINITY, span);
+    let json = dump_tokens(source, &tokens, DumpFormat::Json);
+    assert!(json.contains("\"lexeme\": \"x\", \"literal\": null,"), "{}", json);
+}
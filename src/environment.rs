@@ -0,0 +1,100 @@
+//! Storage for the variables of a running program. Environments form a
+//! chain from the innermost scope out to the globals.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::value::Value;
+
+#[derive(Default)]
+pub struct Environment<'a> {
+    values: HashMap<String, Value<'a>>,
+    enclosing: Option<Rc<RefCell<Environment<'a>>>>,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new() -> Environment<'a> {
+        Environment {
+            values: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    /// A scope nested in `enclosing`, which it falls back to for variables
+    /// it does not declare itself.
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment<'a>>>) -> Environment<'a> {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Declares a variable, replacing any earlier one with the same name.
+    pub fn define(&mut self, name: &str, value: Value<'a>) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value<'a>> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Changes an existing variable. Returns false when it was never
+    /// declared, assignment does not create variables.
+    pub fn assign(&mut self, name: &str, value: Value<'a>) -> bool {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+
+    /// Gets a variable from the scope `depth` steps out, where the resolver
+    /// found it to be declared.
+    pub fn get_at(
+        environment: &Rc<RefCell<Environment<'a>>>,
+        depth: usize,
+        name: &str,
+    ) -> Option<Value<'a>> {
+        Environment::ancestor(environment, depth)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+    }
+
+    /// Changes a variable in the scope `depth` steps out.
+    pub fn assign_at(
+        environment: &Rc<RefCell<Environment<'a>>>,
+        depth: usize,
+        name: &str,
+        value: Value<'a>,
+    ) {
+        Environment::ancestor(environment, depth)
+            .borrow_mut()
+            .values
+            .insert(name.to_string(), value);
+    }
+
+    fn ancestor(
+        environment: &Rc<RefCell<Environment<'a>>>,
+        depth: usize,
+    ) -> Rc<RefCell<Environment<'a>>> {
+        let mut environment = environment.clone();
+        for _ in 0..depth {
+            let enclosing = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("resolved scope is missing");
+            environment = enclosing;
+        }
+        environment
+    }
+}
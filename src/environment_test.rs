@@ -0,0 +1,35 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::value::Value;
+
+#[test]
+fn variables_are_found_in_enclosing_scopes() {
+    let globals = Rc::new(RefCell::new(Environment::new()));
+    globals.borrow_mut().define("a", Value::Number(1.0));
+    let inner = Environment::with_enclosing(globals);
+    assert_eq!(Some(Value::Number(1.0)), inner.get("a"));
+    assert_eq!(None, inner.get("b"));
+}
+
+#[test]
+fn inner_definitions_shadow_outer_ones() {
+    let globals = Rc::new(RefCell::new(Environment::new()));
+    globals.borrow_mut().define("a", Value::Number(1.0));
+    let mut inner = Environment::with_enclosing(globals.clone());
+    inner.define("a", Value::Boolean(true));
+    assert_eq!(Some(Value::Boolean(true)), inner.get("a"));
+    assert_eq!(Some(Value::Number(1.0)), globals.borrow().get("a"));
+}
+
+#[test]
+fn assignment_changes_the_nearest_declaration() {
+    let globals = Rc::new(RefCell::new(Environment::new()));
+    globals.borrow_mut().define("a", Value::Number(1.0));
+    let mut inner = Environment::with_enclosing(globals.clone());
+    assert!(inner.assign("a", Value::Number(2.0)));
+    assert_eq!(Some(Value::Number(2.0)), globals.borrow().get("a"));
+    assert!(!inner.assign("missing", Value::Nil));
+    assert_eq!(None, inner.get("missing"));
+}
@@ -0,0 +1,537 @@
+//! A tree-walking interpreter that runs the statements the parser produces.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::parser::{
+    BinaryExpression, CallExpression, ClassStatement, Expr, InterpolationPart, Literal, Program,
+    Stmt, UnaryExpression,
+};
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use crate::token::TokenType;
+use crate::value::{Class, Function, Instance, Value};
+
+/// An error that stops a running program, like adding a number to a string.
+/// `span` is the token the error is about.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Span,
+    /// The calls that were running, innermost first. Only filled in once
+    /// the error made it out of `Interpreter::interpret`.
+    pub stack: Vec<StackFrame>,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>, span: Span) -> RuntimeError {
+        RuntimeError {
+            message: message.into(),
+            span,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Like the `Display` format, but with each frame at its file, line
+    /// and column, `[lib/util.lox:12:5] in f()`.
+    pub fn describe(&self, source_map: &SourceMap) -> String {
+        let mut out = self.message.clone();
+        for frame in &self.stack {
+            out.push('\n');
+            out.push_str(&frame.describe(source_map));
+        }
+        out
+    }
+}
+
+/// Prints the error and its stack trace in the format of the reference
+/// implementation:
+///
+/// ```text
+/// Operands must be numbers.
+/// [line 2] in inner()
+/// [line 5] in script
+/// ```
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for frame in &self.stack {
+            write!(f, "\n{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// A function that was running, and where in it it was: the error, or
+/// the call to the next function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StackFrame {
+    /// `None` for the top level of the script.
+    pub function: Option<String>,
+    pub span: Span,
+}
+
+impl StackFrame {
+    pub fn describe(&self, source_map: &SourceMap) -> String {
+        self.format(&source_map.describe(self.span))
+    }
+
+    fn format(&self, location: &str) -> String {
+        match &self.function {
+            Some(function) => format!("[{}] in {}()", location, function),
+            None => format!("[{}] in script", location),
+        }
+    }
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.format(&format!("line {}", self.span.line)))
+    }
+}
+
+// A call in progress: the function and where it was called from
+struct Call<'a> {
+    function: &'a str,
+    call_site: Span,
+}
+
+/// How deep calls can nest before the program is stopped with a "Stack
+/// overflow." error.
+pub const MAX_CALL_DEPTH: usize = 5_000;
+
+// Lox calls and nested expressions are Rust recursion, which can need far
+// more stack than the thread running the interpreter has. When less than
+// `RED_ZONE` is left, evaluation carries on in a new `STACK_GROWTH` segment.
+const RED_ZONE: usize = 64 * 1024;
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
+type RuntimeResult<T> = Result<T, RuntimeError>;
+
+// How a statement finished: by running to its end, or by a `return` that
+// has to unwind up to the function call
+enum Flow<'a> {
+    Normal,
+    Return(Value<'a>),
+}
+
+/// Runs programs, writing what they print to `out`.
+pub struct Interpreter<'a, W: Write> {
+    out: W,
+    globals: Rc<RefCell<Environment<'a>>>,
+    environment: Rc<RefCell<Environment<'a>>>,
+    // Outermost first. A call that fails stays on it, so that the stack at
+    // the time of the error can be reported.
+    calls: Vec<Call<'a>>,
+}
+
+impl<'a, W: Write> Interpreter<'a, W> {
+    pub fn new(out: W) -> Interpreter<'a, W> {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        Interpreter {
+            out,
+            globals: globals.clone(),
+            environment: globals,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Runs `program`, which must have been resolved, until it ends or
+    /// fails. Variables it defines stay around for the next program, as a
+    /// REPL needs.
+    pub fn interpret(&mut self, program: &Program<'a>) -> Result<(), RuntimeError> {
+        for stmt in &program.statements {
+            if let Err(mut error) = self.execute(stmt) {
+                error.stack = self.unwind(error.span);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    // Turns the calls that were left running by an error at `span` into a
+    // stack trace. Each function was at the call to the next one.
+    fn unwind(&mut self, mut span: Span) -> Vec<StackFrame> {
+        let mut stack = Vec::with_capacity(self.calls.len() + 1);
+        for call in self.calls.drain(..).rev() {
+            stack.push(StackFrame {
+                function: Some(call.function.to_string()),
+                span,
+            });
+            span = call.call_site;
+        }
+        stack.push(StackFrame {
+            function: None,
+            span,
+        });
+        stack
+    }
+
+    fn execute(&mut self, stmt: &Stmt<'a>) -> RuntimeResult<Flow<'a>> {
+        stacker::maybe_grow(RED_ZONE, STACK_GROWTH, || self.execute_statement(stmt))
+    }
+
+    fn execute_statement(&mut self, stmt: &Stmt<'a>) -> RuntimeResult<Flow<'a>> {
+        match stmt {
+            Stmt::Expression(stmt) => {
+                self.evaluate(&stmt.expression)?;
+            }
+            Stmt::Print(stmt) => {
+                let value = self.evaluate(&stmt.expression)?;
+                writeln!(self.out, "{}", value).map_err(|error| {
+                    RuntimeError::new(format!("Could not print: {}.", error), stmt.span)
+                })?;
+            }
+            Stmt::Var(stmt) => {
+                let value = match &stmt.initializer {
+                    Some(initializer) => self.evaluate(initializer)?,
+                    None => Value::Nil,
+                };
+                self.environment.borrow_mut().define(stmt.name.lexeme, value);
+            }
+            Stmt::Block(stmt) => {
+                let environment = Environment::with_enclosing(self.environment.clone());
+                return self.execute_block(&stmt.statements, environment);
+            }
+            Stmt::If(stmt) => {
+                if self.evaluate(&stmt.condition)?.is_truthy() {
+                    return self.execute(&stmt.then_branch);
+                } else if let Some(else_branch) = &stmt.else_branch {
+                    return self.execute(else_branch);
+                }
+            }
+            Stmt::While(stmt) => {
+                while self.evaluate(&stmt.condition)?.is_truthy() {
+                    if let Flow::Return(value) = self.execute(&stmt.body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Stmt::Function(declaration) => {
+                let function = Function::new(declaration.clone(), self.environment.clone(), false);
+                self.environment
+                    .borrow_mut()
+                    .define(declaration.name.lexeme, Value::Function(Rc::new(function)));
+            }
+            Stmt::Class(stmt) => self.declare_class(stmt)?,
+            Stmt::Return(stmt) => {
+                let value = match &stmt.value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn declare_class(&mut self, stmt: &ClassStatement<'a>) -> RuntimeResult<()> {
+        let superclass = match &stmt.superclass {
+            Some(superclass) => match self.evaluate(superclass)? {
+                Value::Class(class) => Some(class),
+                _ => {
+                    return Err(RuntimeError::new(
+                        "Superclass must be a class.",
+                        superclass.span(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        // Methods of a subclass close over a scope that holds `super`
+        let closure = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::with_enclosing(self.environment.clone());
+                environment.define("super", Value::Class(superclass.clone()));
+                Rc::new(RefCell::new(environment))
+            }
+            None => self.environment.clone(),
+        };
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|method| {
+                let is_initializer = method.name.lexeme == "init";
+                let function = Function::new(method.clone(), closure.clone(), is_initializer);
+                (method.name.lexeme, Rc::new(function))
+            })
+            .collect();
+
+        let class = Class::new(stmt.name.lexeme, superclass, methods);
+        self.environment
+            .borrow_mut()
+            .define(stmt.name.lexeme, Value::Class(Rc::new(class)));
+        Ok(())
+    }
+
+    // Runs `statements` in `environment`, going back to the current one
+    // afterwards however they finish
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt<'a>],
+        environment: Environment<'a>,
+    ) -> RuntimeResult<Flow<'a>> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+        let mut result = Ok(Flow::Normal);
+        for statement in statements {
+            result = self.execute(statement);
+            if !matches!(result, Ok(Flow::Normal)) {
+                break;
+            }
+        }
+        self.environment = previous;
+        result
+    }
+
+    fn evaluate(&mut self, expr: &Expr<'a>) -> RuntimeResult<Value<'a>> {
+        stacker::maybe_grow(RED_ZONE, STACK_GROWTH, || self.evaluate_expression(expr))
+    }
+
+    fn evaluate_expression(&mut self, expr: &Expr<'a>) -> RuntimeResult<Value<'a>> {
+        match expr {
+            Expr::Literal(literal) => match &literal.value {
+                Literal::Variable(name) => {
+                    self.look_up_variable(name, literal.depth).ok_or_else(|| {
+                        RuntimeError::new(format!("Undefined variable '{}'.", name), literal.span)
+                    })
+                }
+                Literal::String(string) => Ok(Value::String(Rc::from(string.as_str()))),
+                Literal::Number(number) => Ok(Value::Number(*number)),
+                Literal::Boolean(boolean) => Ok(Value::Boolean(*boolean)),
+                Literal::Nil(_) => Ok(Value::Nil),
+            },
+            Expr::Grouping(grouping) => self.evaluate(&grouping.expression),
+            Expr::Unary(unary) => self.evaluate_unary(unary),
+            Expr::Binary(binary) => self.evaluate_binary(binary),
+            Expr::Logical(logical) => {
+                // Short-circuits, and the result is the deciding operand itself rather
+                // than a boolean
+                let left = self.evaluate(&logical.left)?;
+                let decided = match logical.operator.token_type {
+                    TokenType::Or => left.is_truthy(),
+                    TokenType::And => !left.is_truthy(),
+                    _ => unreachable!("{:?} is not a logical operator", logical.operator),
+                };
+                if decided {
+                    Ok(left)
+                } else {
+                    self.evaluate(&logical.right)
+                }
+            }
+            Expr::Assign(assign) => {
+                let value = self.evaluate(&assign.value)?;
+                match assign.depth {
+                    Some(depth) => {
+                        Environment::assign_at(&self.environment, depth, &assign.name, value.clone())
+                    }
+                    None => {
+                        if !self.globals.borrow_mut().assign(&assign.name, value.clone()) {
+                            return Err(RuntimeError::new(
+                                format!("Undefined variable '{}'.", assign.name),
+                                assign.span,
+                            ));
+                        }
+                    }
+                }
+                Ok(value)
+            }
+            Expr::Call(call) => self.evaluate_call(call),
+            Expr::Get(get) => match self.evaluate(&get.object)? {
+                Value::Instance(instance) => Instance::get(&instance, get.name.lexeme).ok_or_else(|| {
+                    RuntimeError::new(
+                        format!("Undefined property '{}'.", get.name.lexeme),
+                        get.name.span,
+                    )
+                }),
+                _ => Err(RuntimeError::new("Only instances have properties.", get.name.span)),
+            },
+            Expr::Set(set) => {
+                let instance = match self.evaluate(&set.object)? {
+                    Value::Instance(instance) => instance,
+                    _ => return Err(RuntimeError::new("Only instances have fields.", set.name.span)),
+                };
+                let value = self.evaluate(&set.value)?;
+                instance.borrow_mut().set(set.name.lexeme, value.clone());
+                Ok(value)
+            }
+            Expr::Super(expr) => {
+                // `this` is bound in the scope just inside the one of `super`
+                let superclass = self.look_up_variable("super", expr.depth);
+                let this = self.look_up_variable("this", expr.depth.map(|depth| depth - 1));
+                let (superclass, instance) = match (superclass, this) {
+                    (Some(Value::Class(superclass)), Some(Value::Instance(instance))) => {
+                        (superclass, instance)
+                    }
+                    _ => {
+                        return Err(RuntimeError::new(
+                            "Can't use 'super' in a class with no superclass.",
+                            expr.keyword.span,
+                        ))
+                    }
+                };
+                let method = superclass.find_method(expr.method.lexeme).ok_or_else(|| {
+                    RuntimeError::new(
+                        format!("Undefined property '{}'.", expr.method.lexeme),
+                        expr.method.span,
+                    )
+                })?;
+                Ok(Value::Function(Rc::new(method.bind(instance))))
+            }
+            Expr::This(this) => self.look_up_variable("this", this.depth).ok_or_else(|| {
+                RuntimeError::new("Can't use 'this' outside of a class.", this.keyword.span)
+            }),
+            Expr::Interpolation(interpolation) => {
+                let mut string = String::new();
+                for part in &interpolation.parts {
+                    match part {
+                        InterpolationPart::Literal(literal) => string.push_str(literal),
+                        InterpolationPart::Expr(expr) => {
+                            string.push_str(&self.evaluate(expr)?.to_string())
+                        }
+                    }
+                }
+                Ok(Value::String(Rc::from(string)))
+            }
+        }
+    }
+
+    // Locals are where the resolver found them, anything else is a global
+    fn look_up_variable(&self, name: &str, depth: Option<usize>) -> Option<Value<'a>> {
+        match depth {
+            Some(depth) => Environment::get_at(&self.environment, depth, name),
+            None => self.globals.borrow().get(name),
+        }
+    }
+
+    fn evaluate_call(&mut self, call: &CallExpression<'a>) -> RuntimeResult<Value<'a>> {
+        let callee = self.evaluate(&call.callee)?;
+        let mut arguments = Vec::with_capacity(call.arguments.len());
+        for argument in &call.arguments {
+            arguments.push(self.evaluate(argument)?);
+        }
+
+        let arity = match &callee {
+            Value::Function(function) => function.arity(),
+            Value::Class(class) => class.arity(),
+            _ => {
+                return Err(RuntimeError::new(
+                    "Can only call functions and classes.",
+                    call.paren.span,
+                ))
+            }
+        };
+        if arguments.len() != arity {
+            return Err(RuntimeError::new(
+                format!("Expected {} arguments but got {}.", arity, arguments.len()),
+                call.paren.span,
+            ));
+        }
+        if self.calls.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new("Stack overflow.", call.paren.span));
+        }
+        match callee {
+            Value::Function(function) => self.call_function(&function, arguments, call.paren.span),
+            Value::Class(class) => self.instantiate(class, arguments, call.paren.span),
+            _ => unreachable!(),
+        }
+    }
+
+    // Calling a class creates an instance and runs `init` on it, if the
+    // class has one
+    fn instantiate(
+        &mut self,
+        class: Rc<Class<'a>>,
+        arguments: Vec<Value<'a>>,
+        call_site: Span,
+    ) -> RuntimeResult<Value<'a>> {
+        let instance = Rc::new(RefCell::new(Instance::new(class.clone())));
+        if let Some(init) = class.find_method("init") {
+            self.call_function(&init.bind(instance.clone()), arguments, call_site)?;
+        }
+        Ok(Value::Instance(instance))
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Function<'a>,
+        arguments: Vec<Value<'a>>,
+        call_site: Span,
+    ) -> RuntimeResult<Value<'a>> {
+        let mut environment = Environment::with_enclosing(function.closure.clone());
+        for (param, argument) in function.declaration.params.iter().zip(arguments) {
+            environment.define(param.lexeme, argument);
+        }
+        self.calls.push(Call {
+            function: function.name(),
+            call_site,
+        });
+        let flow = self.execute_block(&function.declaration.body, environment)?;
+        self.calls.pop();
+        if function.is_initializer {
+            // Even an early `return;` gives back the instance
+            return Ok(function.closure.borrow().get("this").unwrap());
+        }
+        match flow {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal => Ok(Value::Nil),
+        }
+    }
+
+    fn evaluate_unary(&mut self, unary: &UnaryExpression<'a>) -> RuntimeResult<Value<'a>> {
+        let right = self.evaluate(&unary.right)?;
+        match unary.operator.token_type {
+            TokenType::Bang => Ok(Value::Boolean(!right.is_truthy())),
+            TokenType::Minus => match right {
+                Value::Number(number) => Ok(Value::Number(-number)),
+                _ => Err(RuntimeError::new(
+                    "Operand must be a number.",
+                    unary.operator.span,
+                )),
+            },
+            _ => unreachable!("{:?} is not a unary operator", unary.operator),
+        }
+    }
+
+    fn evaluate_binary(&mut self, binary: &BinaryExpression<'a>) -> RuntimeResult<Value<'a>> {
+        let left = self.evaluate(&binary.left)?;
+        let right = self.evaluate(&binary.right)?;
+        let operator = &binary.operator;
+        match operator.token_type {
+            TokenType::EqualsEquals => return Ok(Value::Boolean(left == right)),
+            TokenType::BangEquals => return Ok(Value::Boolean(left != right)),
+            TokenType::Plus => {
+                return match (left, right) {
+                    (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left + right)),
+                    (Value::String(left), Value::String(right)) => {
+                        Ok(Value::String(Rc::from(format!("{}{}", left, right))))
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Operands must be two numbers or two strings.",
+                        operator.span,
+                    )),
+                }
+            }
+            _ => (),
+        }
+
+        let (left, right) = match (left, right) {
+            (Value::Number(left), Value::Number(right)) => (left, right),
+            _ => return Err(RuntimeError::new("Operands must be numbers.", operator.span)),
+        };
+        let value = match operator.token_type {
+            TokenType::Minus => Value::Number(left - right),
+            TokenType::Star => Value::Number(left * right),
+            TokenType::Slash => Value::Number(left / right),
+            TokenType::Greater => Value::Boolean(left > right),
+            TokenType::GreaterEqual => Value::Boolean(left >= right),
+            TokenType::Less => Value::Boolean(left < right),
+            TokenType::LessEqual => Value::Boolean(left <= right),
+            _ => unreachable!("{:?} is not a binary operator", operator),
+        };
+        Ok(value)
+    }
+}
@@ -0,0 +1,616 @@
+use crate::interpreter::{Interpreter, RuntimeError, MAX_CALL_DEPTH};
+use crate::parser::{parse, Program};
+use crate::resolver::resolve;
+use crate::scanner::scan_program;
+use crate::source_map::SourceMap;
+use crate::token::Token;
+
+// A program ready to interpret, which must parse and resolve without errors
+fn resolved<'a>(tokens: &[Token<'a>]) -> Program<'a> {
+    let mut program = parse(tokens).unwrap();
+    resolve(&mut program).unwrap();
+    program
+}
+
+fn interpret(source: &str) -> (String, Result<(), RuntimeError>) {
+    let (tokens, errors) = scan_program(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let program = resolved(&tokens);
+    let mut output = Vec::new();
+    let result = Interpreter::new(&mut output).interpret(&program);
+    (String::from_utf8(output).unwrap(), result)
+}
+
+// What the program prints, which must run without errors
+fn run(source: &str) -> String {
+    let (output, result) = interpret(source);
+    result.unwrap();
+    output
+}
+
+fn run_error(source: &str) -> RuntimeError {
+    interpret(source).1.unwrap_err()
+}
+
+#[test]
+fn print_expressions() {
+    assert_eq!(
+        "3\n2.5\nab\ntrue\nnil\nfalse\n",
+        run("print 1 + 2; print 5 / 2; print \"a\" + \"b\"; print 1 < 2; print nil; print !0;")
+    );
+}
+
+#[test]
+fn variables_hold_their_value() {
+    assert_eq!("3\nnil\n", run("var a = 1 + 2; var b; print a; print b;"));
+}
+
+#[test]
+fn interpolation_formats_values() {
+    assert_eq!("1 + 2 = 3!\n", run(r#"var a = 1; print "${a} + 2 = ${a + 2}!";"#));
+}
+
+#[test]
+fn if_runs_the_branch_its_condition_picks() {
+    assert_eq!(
+        "then\nelse\nnil is false\n",
+        run(r#"
+if (1 < 2) print "then"; else print "not then";
+if (1 > 2) print "not else"; else print "else";
+if (nil) print "nil is true"; else if (0) print "nil is false";
+if (false) print "skipped";
+"#)
+    );
+}
+
+#[test]
+fn dangling_else_belongs_to_the_inner_if() {
+    assert_eq!("inner else\n", run("if (true) if (false) print 1; else print \"inner else\";"));
+    assert_eq!("", run("if (false) if (true) print 1; else print 2;"));
+}
+
+#[test]
+fn loops_with_false_conditions_never_run_their_body() {
+    assert_eq!(
+        "done\n",
+        run(r#"
+while (false) print "while";
+for (; false; ) print "for";
+for (var i = 3; i > 5; i + 1) print i;
+print "done";
+"#)
+    );
+}
+
+#[test]
+fn operands_are_type_checked() {
+    let error = run_error("print 1;\nprint \"a\" - 1;");
+    assert_eq!("Operands must be numbers.", error.message);
+    assert_eq!("Operands must be numbers.\n[line 2] in script", error.to_string());
+    assert_eq!(
+        "Operands must be two numbers or two strings.",
+        run_error("print 1 + nil;").message
+    );
+    assert_eq!("Operand must be a number.", run_error("print -\"a\";").message);
+}
+
+#[test]
+fn output_before_an_error_is_kept() {
+    let (output, result) = interpret("print 1;\nprint missing;\nprint 2;");
+    assert_eq!("1\n", output);
+    assert_eq!("Undefined variable 'missing'.", result.unwrap_err().message);
+}
+
+#[test]
+fn logical_operators_return_the_deciding_operand() {
+    assert_eq!(
+        "hi\n1\nnil\nfalse\nyes\n2\n",
+        run(r#"print "hi" or 2; print nil or 1; print nil and 1; print false and nil; print false or "yes"; print 1 and 2;"#)
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    // Evaluating the undefined variable would be an error
+    assert_eq!("true\nfalse\n", run("print true or missing; print false and missing;"));
+    assert_eq!("Undefined variable 'missing'.", run_error("print false or missing;").message);
+}
+
+#[test]
+fn assignment_changes_variables_and_yields_the_value() {
+    assert_eq!("2\n3\n3\n", run("var a = 1; a = 2; print a; var b; print a = b = 3; print b;"));
+}
+
+#[test]
+fn assigning_an_undeclared_variable_is_an_error() {
+    let error = run_error("\nmissing = 1;");
+    assert_eq!("Undefined variable 'missing'.\n[line 2] in script", error.to_string());
+}
+
+#[test]
+fn for_loops_count() {
+    assert_eq!(
+        "0\n1\n2\n",
+        run("for (var i = 0; i < 3; i = i + 1) print i;")
+    );
+    assert_eq!(
+        "1\n2\n6\n24\n",
+        run("var product = 1; var n = 1; while (n < 5) { product = product * n; print product; n = n + 1; }")
+    );
+}
+
+#[test]
+fn functions_take_arguments_and_return_values() {
+    assert_eq!(
+        "3\nnil\nnil\n<fn add>\n",
+        run(r#"
+fun add(a, b) { return a + b; }
+fun nothing() {}
+fun early() { return; print "unreachable"; }
+print add(1, 2);
+print nothing();
+print early();
+print add;
+"#)
+    );
+}
+
+#[test]
+fn functions_recurse() {
+    assert_eq!(
+        "55\n",
+        run("fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } print fib(10);")
+    );
+}
+
+#[test]
+fn return_unwinds_out_of_loops() {
+    assert_eq!(
+        "3\n",
+        run("fun first(limit) { var i = 0; while (true) { i = i + 1; if (i >= limit) return i; } } print first(3);")
+    );
+}
+
+#[test]
+fn closures_keep_their_environment() {
+    assert_eq!(
+        "1\n2\n1\n",
+        run(r#"
+fun makeCounter() {
+    var count = 0;
+    fun counter() {
+        count = count + 1;
+        return count;
+    }
+    return counter;
+}
+var a = makeCounter();
+var b = makeCounter();
+print a();
+print a();
+print b();
+"#)
+    );
+}
+
+#[test]
+fn parameters_do_not_leak_out_of_calls() {
+    let error = run_error("fun f(param) {}\nf(1);\nprint param;");
+    assert_eq!("Undefined variable 'param'.\n[line 3] in script", error.to_string());
+}
+
+#[test]
+fn functions_are_equal_only_to_themselves() {
+    assert_eq!(
+        "true\nfalse\n",
+        run("fun f() {} fun g() {} var h = f; print f == h; print f == g;")
+    );
+}
+
+#[test]
+fn calls_are_checked() {
+    assert_eq!(
+        "Expected 2 arguments but got 1.",
+        run_error("fun f(a, b) {}\nf(1);").message
+    );
+    let error = run_error("var x = 1;\nx(2);");
+    assert_eq!("Can only call functions and classes.\n[line 2] in script", error.to_string());
+    assert_eq!(
+        "Can only call functions and classes.",
+        run_error("\"str\"();").message
+    );
+}
+
+#[test]
+fn classes_create_instances_with_fields() {
+    assert_eq!(
+        "Bag\nBag instance\n1\n3\n",
+        run(r#"
+class Bag {}
+var bag = Bag();
+print Bag;
+print bag;
+bag.item = 1;
+print bag.item;
+bag.item = bag.item + 2;
+print bag.item;
+"#)
+    );
+}
+
+#[test]
+fn methods_are_bound_to_their_instance() {
+    assert_eq!(
+        "Hello, Ann\nHello, Bob\nHello, Ann\n",
+        run(r#"
+class Greeter {
+    greet() { print "Hello, " + this.name; }
+}
+var ann = Greeter();
+ann.name = "Ann";
+var bob = Greeter();
+bob.name = "Bob";
+var greet = ann.greet;
+ann.greet();
+bob.greet();
+greet();
+"#)
+    );
+}
+
+#[test]
+fn this_in_closures_refers_to_the_instance() {
+    assert_eq!(
+        "Thing instance\n",
+        run("class Thing { getCallback() { fun callback() { print this; } return callback; } } Thing().getCallback()();")
+    );
+}
+
+#[test]
+fn initializers_set_up_instances() {
+    assert_eq!(
+        "3\n4\n",
+        run(r#"
+class Point {
+    init(x, y) { this.x = x; this.y = y; }
+    sum() { return this.x + this.y; }
+}
+var point = Point(1, 2);
+print point.sum();
+point.x = 2;
+print point.sum();
+"#)
+    );
+}
+
+#[test]
+fn init_always_returns_this() {
+    assert_eq!(
+        "Foo instance\nFoo instance\ntrue\n",
+        run(r#"
+class Foo {
+    init(early) { if (early) return; this.late = true; }
+}
+var foo = Foo(true);
+print foo;
+print foo.init(false);
+print foo.late;
+"#)
+    );
+}
+
+#[test]
+fn fields_shadow_methods() {
+    assert_eq!(
+        "field\n",
+        run("class A { m() { return \"method\"; } } var a = A(); a.m = \"field\"; print a.m;")
+    );
+}
+
+#[test]
+fn property_errors() {
+    assert_eq!(
+        "Undefined property 'missing'.\n[line 2] in script",
+        run_error("class A {}\nprint A().missing;").to_string()
+    );
+    assert_eq!("Only instances have properties.", run_error("var x = 1; print x.y;").message);
+    assert_eq!("Only instances have fields.", run_error("var x = \"s\"; x.y = 1;").message);
+    assert_eq!(
+        "Expected 2 arguments but got 0.",
+        run_error("class P { init(x, y) {} } P();").message
+    );
+    assert_eq!("Expected 0 arguments but got 1.", run_error("class E {} E(1);").message);
+}
+
+#[test]
+fn subclasses_inherit_methods() {
+    assert_eq!(
+        "A.a\nB.b\nA.a\n",
+        run(r#"
+class A { a() { print "A.a"; } }
+class B < A { b() { print "B.b"; } }
+class C < B {}
+B().a();
+C().b();
+C().a();
+"#)
+    );
+}
+
+#[test]
+fn super_calls_the_superclass_method_on_this() {
+    assert_eq!(
+        "B.m\nA.m on C instance\n",
+        run(r#"
+class A { m() { print "A.m on " + this.name; } }
+class B < A { m() { print "B.m"; super.m(); } }
+class C < B { init() { this.name = "C instance"; } }
+C().m();
+"#)
+    );
+}
+
+#[test]
+fn super_is_bound_where_the_method_is_declared() {
+    // `super` in B's method is A even when called on an instance of C
+    assert_eq!(
+        "A\n",
+        run(r#"
+class A { say() { print "A"; } }
+class B < A { test() { super.say(); } say() { print "B"; } }
+class C < B { say() { print "C"; } }
+C().test();
+"#)
+    );
+}
+
+#[test]
+fn initializers_are_inherited() {
+    assert_eq!(
+        "1\n",
+        run("class A { init(x) { this.x = x; } } class B < A {} print B(1).x;")
+    );
+}
+
+#[test]
+fn inheritance_errors() {
+    assert_eq!(
+        "Superclass must be a class.\n[line 2] in script",
+        run_error("var NotAClass = \"so not a class\";\nclass Sub < NotAClass {}").to_string()
+    );
+    assert_eq!(
+        "Undefined property 'missing'.",
+        run_error("class A {} class B < A { m() { super.missing(); } } B().m();").message
+    );
+}
+
+#[test]
+fn numbers_print_like_the_reference_implementation() {
+    assert_eq!(
+        "3\n-2.5\n0.30000000000000004\n-0\n1234567\n1.0E7\n1.5E21\n0.001\n1.0E-4\nNaN\nInfinity\n-Infinity\n",
+        run(r#"
+print 3.0;
+print -2.5;
+print 0.1 + 0.2;
+print -0;
+print 1234567;
+print 10000000;
+print 1.5e21;
+print 0.001;
+print 0.0001;
+print 0 / 0;
+print 1 / 0;
+print -1 / 0;
+"#)
+    );
+}
+
+#[test]
+fn equality_follows_lox_rules() {
+    assert_eq!(
+        "true\ntrue\nfalse\nfalse\nfalse\ntrue\nfalse\n",
+        run(r#"
+print nil == nil;
+print "a" + "b" == "ab";
+print 1 == "1";
+print nil == false;
+print 0 == false;
+print 1 != 2;
+print 0 / 0 == 0 / 0;
+"#)
+    );
+}
+
+#[test]
+fn only_nil_and_false_are_falsey() {
+    assert_eq!(
+        "false\nfalse\ntrue\ntrue\ntrue\n",
+        run(r#"print !!nil; print !!false; print !!0; print !!""; print !!"false";"#)
+    );
+}
+
+#[test]
+fn strings_concatenate_and_compare() {
+    assert_eq!("foobar\ntrue\n", run(r#"var a = "foo"; print a + "bar"; print a == "fo" + "o";"#));
+    assert_eq!(
+        "Operands must be numbers.",
+        run_error(r#"print "a" < "b";"#).message
+    );
+}
+
+#[test]
+fn runtime_errors_report_the_calls_they_happened_in() {
+    let error = run_error(
+        r#"
+fun inner(x) {
+    return x + nil;
+}
+fun outer() {
+    print "before";
+    return inner(1);
+}
+outer();
+"#,
+    );
+    assert_eq!(
+        "Operands must be two numbers or two strings.\n[line 3] in inner()\n[line 7] in outer()\n[line 9] in script",
+        error.to_string()
+    );
+    assert_eq!(3, error.stack.len());
+}
+
+#[test]
+fn stack_frames_know_their_file() {
+    let mut source_map = SourceMap::new();
+    let lib = source_map.add("lib/util.lox", "fun half(x) {\n    return x / nil;\n}");
+    let main = source_map.add("main.lox", "print 1;\nhalf(4);");
+    let (lib_tokens, _) = source_map.scan(lib);
+    let (main_tokens, _) = source_map.scan(main);
+    let lib_program = resolved(&lib_tokens);
+    let main_program = resolved(&main_tokens);
+
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::new(&mut output);
+    interpreter.interpret(&lib_program).unwrap();
+    let error = interpreter.interpret(&main_program).unwrap_err();
+    assert_eq!(
+        "Operands must be numbers.\n[line 2] in half()\n[line 2] in script",
+        error.to_string()
+    );
+    assert_eq!(
+        "Operands must be numbers.\n[lib/util.lox:2:14] in half()\n[main.lox:2:7] in script",
+        error.describe(&source_map)
+    );
+}
+
+#[test]
+fn stack_traces_name_methods_and_initializers() {
+    let error = run_error("class A {\n  init() { this.fail(); }\n  fail() { -\"a\"; }\n}\nA();");
+    assert_eq!(
+        "Operand must be a number.\n[line 3] in fail()\n[line 2] in init()\n[line 5] in script",
+        error.to_string()
+    );
+}
+
+#[test]
+fn recursion_runs_up_to_the_call_limit() {
+    let source = format!(
+        "fun count(n) {{ if (n > 0) return count(n - 1); return n; }}\nprint count({});",
+        MAX_CALL_DEPTH - 1
+    );
+    assert_eq!("0\n", run(&source));
+}
+
+#[test]
+fn unbounded_recursion_overflows_the_lox_stack() {
+    let error = run_error("fun f() { f(); }\nf();");
+    assert_eq!("Stack overflow.", error.message);
+    // Every call to `f`, and the script
+    assert_eq!(MAX_CALL_DEPTH + 1, error.stack.len());
+}
+
+#[test]
+fn the_interpreter_keeps_working_after_an_error() {
+    let source = "fun f(x) { return -x; }\nf(\"a\");";
+    let (tokens, _) = scan_program(source);
+    let failing = resolved(&tokens);
+    let (tokens, _) = scan_program("print f(2);");
+    let passing = resolved(&tokens);
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::new(&mut output);
+    assert_eq!(
+        "Operand must be a number.\n[line 1] in f()\n[line 2] in script",
+        interpreter.interpret(&failing).unwrap_err().to_string()
+    );
+    interpreter.interpret(&passing).unwrap();
+    assert_eq!("-2\n", String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn blocks_shadow_and_restore_variables() {
+    assert_eq!(
+        "inner a\nouter b\nglobal c\nouter a\nouter b\nglobal c\nglobal a\nglobal b\nglobal c\n",
+        run(r#"
+var a = "global a";
+var b = "global b";
+var c = "global c";
+{
+    var a = "outer a";
+    var b = "outer b";
+    {
+        var a = "inner a";
+        print a;
+        print b;
+        print c;
+    }
+    print a;
+    print b;
+    print c;
+}
+print a;
+print b;
+print c;
+"#)
+    );
+}
+
+#[test]
+fn assignment_in_a_block_changes_the_outer_variable() {
+    assert_eq!("2\n", run("var a = 1; { a = 2; } print a;"));
+}
+
+#[test]
+fn block_variables_go_out_of_scope() {
+    let error = run_error("{\n    var hidden = 1;\n}\nprint hidden;");
+    assert_eq!("Undefined variable 'hidden'.\n[line 4] in script", error.to_string());
+}
+
+#[test]
+fn globals_can_be_redefined() {
+    assert_eq!("1\n2\n", run("var a = 1; print a; var a = 2; print a;"));
+}
+
+#[test]
+fn initializers_cannot_read_the_variable_they_shadow() {
+    let (tokens, _) = scan_program("var a = 1; { var a = a + 1; print a; }");
+    let mut program = parse(&tokens).unwrap();
+    let errors = resolve(&mut program).unwrap_err();
+    assert_eq!("Can't read local variable in its own initializer.", errors[0].message);
+}
+
+#[test]
+fn closures_see_the_variables_in_scope_where_they_are_declared() {
+    assert_eq!(
+        "global\nglobal\n",
+        run(r#"
+var a = "global";
+{
+    fun showA() { print a; }
+    showA();
+    var a = "block";
+    showA();
+}
+"#)
+    );
+}
+
+#[test]
+fn closures_assign_the_variable_they_captured() {
+    assert_eq!(
+        "10\n3\n",
+        run(r#"
+fun outer() {
+    var x = 1;
+    fun increment() { x = x + 1; }
+    {
+        var x = 10;
+        increment();
+        print x;
+    }
+    increment();
+    print x;
+}
+outer();
+"#)
+    );
+}
@@ -0,0 +1,31 @@
+pub mod cst;
+pub mod dump;
+pub mod environment;
+pub mod interpreter;
+pub mod parser;
+pub mod relex;
+pub mod resolver;
+pub mod scanner;
+pub mod source_map;
+pub mod span;
+pub mod token;
+pub mod value;
+
+#[cfg(test)]
+mod cst_test;
+#[cfg(test)]
+mod dump_test;
+#[cfg(test)]
+mod environment_test;
+#[cfg(test)]
+mod interpreter_test;
+#[cfg(test)]
+mod parser_test;
+#[cfg(test)]
+mod relex_test;
+#[cfg(test)]
+mod resolver_test;
+#[cfg(test)]
+mod scanner_test;
+#[cfg(test)]
+mod source_map_test;
@@ -1,16 +1,111 @@
-mod scanner;
-mod token;
-mod parser;
+use std::env;
+use std::io;
+use std::process;
+
+use rslox::dump::{dump_tokens, DumpFormat};
+use rslox::interpreter::Interpreter;
+use rslox::parser::parse;
+use rslox::resolver::resolve;
+use rslox::source_map::SourceMap;
+use rslox::span::FileId;
+
+const USAGE: &str = "Usage: rslox [script]\n       rslox tokens [--json] <file>";
+
+// Exit codes from sysexits.h, as the reference implementation uses them
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
 
 fn main() {
-    println!("rslox 0.0.1");
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => println!("rslox 0.0.1"),
+        Some("tokens") => tokens(&args[1..]),
+        Some(_) if args.len() == 1 => process::exit(run_file(&args[0])),
+        Some(_) => usage(),
+    }
+}
+
+// rslox <script>
+fn run_file(path: &str) -> i32 {
+    let mut source_map = SourceMap::new();
+    let file = load(&mut source_map, path);
+    let (tokens, scan_errors) = source_map.scan(file);
+    for error in &scan_errors {
+        eprintln!("{}: Error: {}", source_map.describe(error.span), error.message());
+    }
+
+    let mut program = match parse(&tokens) {
+        Ok(program) => program,
+        Err(parse_errors) => {
+            for error in &parse_errors {
+                eprintln!("{}: {}", source_map.describe(error.span), error.summary());
+            }
+            return EX_DATAERR;
+        }
+    };
+    // Scan errors leave out bits of the program, so it must not run even
+    // when what was left parses
+    if !scan_errors.is_empty() {
+        return EX_DATAERR;
+    }
+    if let Err(resolve_errors) = resolve(&mut program) {
+        for error in &resolve_errors {
+            eprintln!("{}: {}", source_map.describe(error.span), error.summary());
+        }
+        return EX_DATAERR;
+    }
+
+    let mut interpreter = Interpreter::new(io::stdout().lock());
+    match interpreter.interpret(&program) {
+        Ok(()) => 0,
+        Err(error) => {
+            // The reference implementation's `[line N]` trace. With only the
+            // script loaded, `error.describe` would add nothing but its name.
+            eprintln!("{}", error);
+            EX_SOFTWARE
+        }
+    }
+}
+
+// rslox tokens [--json] <file>
+fn tokens(args: &[String]) {
+    let (format, path) = match args {
+        [path] => (DumpFormat::Text, path),
+        [flag, path] if flag == "--json" => (DumpFormat::Json, path),
+        _ => usage(),
+    };
+
+    let mut source_map = SourceMap::new();
+    let file = load(&mut source_map, path);
+    let (tokens, errors) = source_map.scan(file);
+    print!("{}", dump_tokens(source_map.source(file), &tokens, format));
+
+    for error in &errors {
+        eprintln!("{}: Error: {}", source_map.describe(error.span), error.message());
+    }
+    if !errors.is_empty() {
+        process::exit(EX_DATAERR);
+    }
+}
+
+fn load(source_map: &mut SourceMap, path: &str) -> FileId {
+    match source_map.load(path) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", path, error);
+            process::exit(EX_NOINPUT);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(EX_USAGE);
 }
 
 mod test {
     #[test]
     fn main_test() {}
 }
-
-#[cfg(test)]
-mod scanner_test;
-mod parser_test;
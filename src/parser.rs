@@ -1,18 +1,95 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::span::Span;
 use crate::token::{Token, TokenType, Value};
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum Expr {
-    Equality(EqualityExpression),
-    Comparison(ComparisonExpression),
-    Term(TermExpression),
-    Factor(FactorExpression),
-    Unary(UnaryExpression),
-    Binary(BinaryExpression),
+pub enum Expr<'a> {
+    Unary(UnaryExpression<'a>),
+    Binary(BinaryExpression<'a>),
+    Logical(LogicalExpression<'a>),
+    Assign(AssignExpression<'a>),
+    Call(CallExpression<'a>),
+    Get(GetExpression<'a>),
+    Set(SetExpression<'a>),
+    This(ThisExpression<'a>),
+    Super(SuperExpression<'a>),
+    Grouping(GroupingExpression<'a>),
     Literal(LiteralExpression),
+    Interpolation(InterpolationExpression<'a>),
+}
+
+impl Expr<'_> {
+    /// The source range this expression was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Unary(expr) => expr.span,
+            Expr::Binary(expr) => expr.span,
+            Expr::Logical(expr) => expr.span,
+            Expr::Assign(expr) => expr.span,
+            Expr::Call(expr) => expr.span,
+            Expr::Get(expr) => expr.span,
+            Expr::Set(expr) => expr.span,
+            Expr::This(expr) => expr.keyword.span,
+            Expr::Super(expr) => expr.keyword.span.to(expr.method.span),
+            Expr::Grouping(expr) => expr.span,
+            Expr::Literal(expr) => expr.span,
+            Expr::Interpolation(expr) => expr.span,
+        }
+    }
+}
+
+/// Prints the expression as an s-expression that makes its structure
+/// explicit, `(* (group (+ 1 2)) 3)`.
+impl fmt::Display for Expr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Unary(expr) => write!(f, "({} {})", expr.operator.lexeme, expr.right),
+            Expr::Binary(expr) => {
+                write!(f, "({} {} {})", expr.operator.lexeme, expr.left, expr.right)
+            }
+            Expr::Logical(expr) => {
+                write!(f, "({} {} {})", expr.operator.lexeme, expr.left, expr.right)
+            }
+            Expr::Assign(expr) => write!(f, "(= {} {})", expr.name, expr.value),
+            Expr::Call(expr) => {
+                write!(f, "(call {}", expr.callee)?;
+                for argument in &expr.arguments {
+                    write!(f, " {}", argument)?;
+                }
+                f.write_str(")")
+            }
+            Expr::Get(expr) => write!(f, "(get {} {})", expr.object, expr.name.lexeme),
+            Expr::Set(expr) => {
+                write!(f, "(set {} {} {})", expr.object, expr.name.lexeme, expr.value)
+            }
+            Expr::This(_) => f.write_str("this"),
+            Expr::Super(expr) => write!(f, "(super {})", expr.method.lexeme),
+            Expr::Grouping(expr) => write!(f, "(group {})", expr.expression),
+            Expr::Literal(expr) => match &expr.value {
+                Literal::Variable(name) => f.write_str(name),
+                Literal::String(string) => write!(f, "{:?}", string),
+                Literal::Number(number) => write!(f, "{}", number),
+                Literal::Boolean(boolean) => write!(f, "{}", boolean),
+                Literal::Nil(nil) => f.write_str(nil),
+            },
+            Expr::Interpolation(expr) => {
+                f.write_str("(interpolate")?;
+                for part in &expr.parts {
+                    match part {
+                        InterpolationPart::Literal(string) => write!(f, " {:?}", string)?,
+                        InterpolationPart::Expr(expr) => write!(f, " {}", expr)?,
+                    }
+                }
+                f.write_str(")")
+            }
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum LiteralExpression {
+pub enum Literal {
     Variable(String),
     String(String),
     Number(f64),
@@ -21,73 +98,600 @@ pub enum LiteralExpression {
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct UnaryExpression {
-    operator: Token,
-    right: Box<Expr>,
+pub struct LiteralExpression {
+    pub value: Literal,
+    pub span: Span,
+    /// For a variable, how many scopes out from here it was declared, or
+    /// `None` for a global. Filled in by the resolver.
+    pub depth: Option<usize>,
+}
+
+impl LiteralExpression {
+    pub fn new(value: Literal, span: Span) -> LiteralExpression {
+        LiteralExpression {
+            value,
+            span,
+            depth: None,
+        }
+    }
 }
 
-impl UnaryExpression {
-    pub fn new(operator: Token, right: Box<Expr>) -> UnaryExpression {
+#[derive(Clone, PartialEq, Debug)]
+pub enum InterpolationPart<'a> {
+    Literal(String),
+    Expr(Expr<'a>),
+}
+
+/// A string literal with embedded expressions, "Hello ${name}!"
+#[derive(Clone, PartialEq, Debug)]
+pub struct InterpolationExpression<'a> {
+    pub parts: Vec<InterpolationPart<'a>>,
+    pub span: Span,
+}
+
+impl<'a> InterpolationExpression<'a> {
+    pub fn new(parts: Vec<InterpolationPart<'a>>, span: Span) -> InterpolationExpression<'a> {
+        InterpolationExpression { parts, span }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnaryExpression<'a> {
+    pub operator: Token<'a>,
+    pub right: Box<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> UnaryExpression<'a> {
+    pub fn new(operator: Token<'a>, right: Box<Expr<'a>>) -> UnaryExpression<'a> {
+        let span = operator.span.to(right.span());
         UnaryExpression {
             operator,
-            right
+            right,
+            span,
         }
     }
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct BinaryExpression {
-    left: Box<Expr>,
-    operator: Token,
-    right: Box<Expr>,
+pub struct BinaryExpression<'a> {
+    pub left: Box<Expr<'a>>,
+    pub operator: Token<'a>,
+    pub right: Box<Expr<'a>>,
+    pub span: Span,
 }
 
-impl BinaryExpression {
-    pub fn new(left: Box<Expr>, operator: Token, right: Box<Expr>) -> BinaryExpression {
+impl<'a> BinaryExpression<'a> {
+    pub fn new(left: Box<Expr<'a>>, operator: Token<'a>, right: Box<Expr<'a>>) -> BinaryExpression<'a> {
+        let span = left.span().to(right.span());
         BinaryExpression {
             left,
             operator,
-            right
+            right,
+            span,
+        }
+    }
+}
+
+/// `and` and `or`. Unlike binary operators they may not evaluate their right
+/// operand, so they get their own node.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LogicalExpression<'a> {
+    pub left: Box<Expr<'a>>,
+    pub operator: Token<'a>,
+    pub right: Box<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> LogicalExpression<'a> {
+    pub fn new(left: Box<Expr<'a>>, operator: Token<'a>, right: Box<Expr<'a>>) -> LogicalExpression<'a> {
+        let span = left.span().to(right.span());
+        LogicalExpression {
+            left,
+            operator,
+            right,
+            span,
+        }
+    }
+}
+
+/// `name = value`. The span covers the whole assignment, from the name to
+/// the end of the value.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AssignExpression<'a> {
+    pub name: String,
+    pub value: Box<Expr<'a>>,
+    pub span: Span,
+    pub depth: Option<usize>,
+}
+
+impl<'a> AssignExpression<'a> {
+    pub fn new(name: String, value: Box<Expr<'a>>, span: Span) -> AssignExpression<'a> {
+        AssignExpression {
+            name,
+            value,
+            span,
+            depth: None,
+        }
+    }
+}
+
+/// `callee(arguments)`. `paren` is the closing parenthesis, where errors
+/// about the call are reported.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CallExpression<'a> {
+    pub callee: Box<Expr<'a>>,
+    pub paren: Token<'a>,
+    pub arguments: Vec<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> CallExpression<'a> {
+    pub fn new(callee: Box<Expr<'a>>, paren: Token<'a>, arguments: Vec<Expr<'a>>) -> CallExpression<'a> {
+        let span = callee.span().to(paren.span);
+        CallExpression {
+            callee,
+            paren,
+            arguments,
+            span,
+        }
+    }
+}
+
+/// `object.name`, reading a property.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GetExpression<'a> {
+    pub object: Box<Expr<'a>>,
+    pub name: Token<'a>,
+    pub span: Span,
+}
+
+impl<'a> GetExpression<'a> {
+    pub fn new(object: Box<Expr<'a>>, name: Token<'a>) -> GetExpression<'a> {
+        let span = object.span().to(name.span);
+        GetExpression { object, name, span }
+    }
+}
+
+/// `object.name = value`, writing a field.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SetExpression<'a> {
+    pub object: Box<Expr<'a>>,
+    pub name: Token<'a>,
+    pub value: Box<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> SetExpression<'a> {
+    pub fn new(object: Box<Expr<'a>>, name: Token<'a>, value: Box<Expr<'a>>) -> SetExpression<'a> {
+        let span = object.span().to(value.span());
+        SetExpression {
+            object,
+            name,
+            value,
+            span,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ThisExpression<'a> {
+    pub keyword: Token<'a>,
+    pub depth: Option<usize>,
+}
+
+impl<'a> ThisExpression<'a> {
+    pub fn new(keyword: Token<'a>) -> ThisExpression<'a> {
+        ThisExpression {
+            keyword,
+            depth: None,
+        }
+    }
+}
+
+/// `super.method`, a method of the superclass bound to `this`. It spans
+/// from the keyword to the method name.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SuperExpression<'a> {
+    pub keyword: Token<'a>,
+    pub method: Token<'a>,
+    pub depth: Option<usize>,
+}
+
+impl<'a> SuperExpression<'a> {
+    pub fn new(keyword: Token<'a>, method: Token<'a>) -> SuperExpression<'a> {
+        SuperExpression {
+            keyword,
+            method,
+            depth: None,
         }
     }
 }
 
+/// A parenthesized expression. It only matters for the span and for
+/// printing, the tree's shape already carries the precedence.
 #[derive(Clone, PartialEq, Debug)]
-pub struct FactorExpression {
+pub struct GroupingExpression<'a> {
+    pub expression: Box<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> GroupingExpression<'a> {
+    pub fn new(expression: Box<Expr<'a>>, span: Span) -> GroupingExpression<'a> {
+        GroupingExpression { expression, span }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct TermExpression {
+pub enum Stmt<'a> {
+    Expression(ExpressionStatement<'a>),
+    Print(PrintStatement<'a>),
+    Var(VarStatement<'a>),
+    Block(BlockStatement<'a>),
+    If(IfStatement<'a>),
+    While(WhileStatement<'a>),
+    Function(Rc<FunctionStatement<'a>>),
+    Return(ReturnStatement<'a>),
+    Class(ClassStatement<'a>),
+}
+
+impl Stmt<'_> {
+    /// The source range this statement was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression(stmt) => stmt.span,
+            Stmt::Print(stmt) => stmt.span,
+            Stmt::Var(stmt) => stmt.span,
+            Stmt::Block(stmt) => stmt.span,
+            Stmt::If(stmt) => stmt.span,
+            Stmt::While(stmt) => stmt.span,
+            Stmt::Function(stmt) => stmt.span,
+            Stmt::Return(stmt) => stmt.span,
+            Stmt::Class(stmt) => stmt.span,
+        }
+    }
+}
+
+/// Prints the statement in the same s-expression style as expressions.
+impl fmt::Display for Stmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stmt::Expression(stmt) => write!(f, "(; {})", stmt.expression),
+            Stmt::Print(stmt) => write!(f, "(print {})", stmt.expression),
+            Stmt::Var(stmt) => match &stmt.initializer {
+                Some(initializer) => write!(f, "(var {} {})", stmt.name.lexeme, initializer),
+                None => write!(f, "(var {})", stmt.name.lexeme),
+            },
+            Stmt::Block(stmt) => {
+                f.write_str("(block")?;
+                for statement in &stmt.statements {
+                    write!(f, " {}", statement)?;
+                }
+                f.write_str(")")
+            }
+            Stmt::If(stmt) => match &stmt.else_branch {
+                Some(else_branch) => write!(
+                    f,
+                    "(if {} {} {})",
+                    stmt.condition, stmt.then_branch, else_branch
+                ),
+                None => write!(f, "(if {} {})", stmt.condition, stmt.then_branch),
+            },
+            Stmt::While(stmt) => write!(f, "(while {} {})", stmt.condition, stmt.body),
+            Stmt::Function(stmt) => stmt.fmt(f),
+            Stmt::Return(stmt) => match &stmt.value {
+                Some(value) => write!(f, "(return {})", value),
+                None => f.write_str("(return)"),
+            },
+            Stmt::Class(stmt) => {
+                write!(f, "(class {}", stmt.name.lexeme)?;
+                if let Some(superclass) = &stmt.superclass {
+                    write!(f, " < {}", superclass)?;
+                }
+                for method in &stmt.methods {
+                    write!(f, " {}", method)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// An expression evaluated for its side effects, `call();`
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExpressionStatement<'a> {
+    pub expression: Expr<'a>,
+    pub span: Span,
+}
+
+impl<'a> ExpressionStatement<'a> {
+    pub fn new(expression: Expr<'a>, span: Span) -> ExpressionStatement<'a> {
+        ExpressionStatement { expression, span }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct ComparisonExpression {
+pub struct PrintStatement<'a> {
+    pub expression: Expr<'a>,
+    pub span: Span,
+}
+
+impl<'a> PrintStatement<'a> {
+    pub fn new(expression: Expr<'a>, span: Span) -> PrintStatement<'a> {
+        PrintStatement { expression, span }
+    }
 }
 
+/// `var name = initializer;`, where the initializer is optional. `doc` holds
+/// the `///` comments right above the declaration, one line each.
 #[derive(Clone, PartialEq, Debug)]
-pub struct EqualityExpression {
+pub struct VarStatement<'a> {
+    pub name: Token<'a>,
+    pub initializer: Option<Expr<'a>>,
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
-struct Parser<'a> {
-    tokens: &'a Vec<Token>,
-    expressions: Vec<Expr>,
+impl<'a> VarStatement<'a> {
+    pub fn new(
+        name: Token<'a>,
+        initializer: Option<Expr<'a>>,
+        doc: Option<String>,
+        span: Span,
+    ) -> VarStatement<'a> {
+        VarStatement {
+            name,
+            initializer,
+            doc,
+            span,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BlockStatement<'a> {
+    pub statements: Vec<Stmt<'a>>,
+    pub span: Span,
+}
+
+impl<'a> BlockStatement<'a> {
+    pub fn new(statements: Vec<Stmt<'a>>, span: Span) -> BlockStatement<'a> {
+        BlockStatement { statements, span }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct IfStatement<'a> {
+    pub condition: Expr<'a>,
+    pub then_branch: Box<Stmt<'a>>,
+    pub else_branch: Option<Box<Stmt<'a>>>,
+    pub span: Span,
+}
+
+impl<'a> IfStatement<'a> {
+    pub fn new(
+        condition: Expr<'a>,
+        then_branch: Box<Stmt<'a>>,
+        else_branch: Option<Box<Stmt<'a>>>,
+        span: Span,
+    ) -> IfStatement<'a> {
+        IfStatement {
+            condition,
+            then_branch,
+            else_branch,
+            span,
+        }
+    }
+}
+
+/// A `while` loop. `for` loops are desugared into one of these.
+#[derive(Clone, PartialEq, Debug)]
+pub struct WhileStatement<'a> {
+    pub condition: Expr<'a>,
+    pub body: Box<Stmt<'a>>,
+    pub span: Span,
+}
+
+impl<'a> WhileStatement<'a> {
+    pub fn new(condition: Expr<'a>, body: Box<Stmt<'a>>, span: Span) -> WhileStatement<'a> {
+        WhileStatement {
+            condition,
+            body,
+            span,
+        }
+    }
+}
+
+/// `fun name(params) { body }`. Shared through an `Rc` because every
+/// function value created from the declaration points back to it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FunctionStatement<'a> {
+    pub name: Token<'a>,
+    pub params: Vec<Token<'a>>,
+    pub body: Vec<Stmt<'a>>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl<'a> FunctionStatement<'a> {
+    pub fn new(
+        name: Token<'a>,
+        params: Vec<Token<'a>>,
+        body: Vec<Stmt<'a>>,
+        doc: Option<String>,
+        span: Span,
+    ) -> FunctionStatement<'a> {
+        FunctionStatement {
+            name,
+            params,
+            body,
+            doc,
+            span,
+        }
+    }
+}
+
+impl fmt::Display for FunctionStatement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<&str> = self.params.iter().map(|param| param.lexeme).collect();
+        write!(f, "(fun {} ({})", self.name.lexeme, params.join(" "))?;
+        for statement in &self.body {
+            write!(f, " {}", statement)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReturnStatement<'a> {
+    pub keyword: Token<'a>,
+    pub value: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> ReturnStatement<'a> {
+    pub fn new(keyword: Token<'a>, value: Option<Expr<'a>>, span: Span) -> ReturnStatement<'a> {
+        ReturnStatement {
+            keyword,
+            value,
+            span,
+        }
+    }
+}
+
+/// `class Name < Superclass { methods }`, where the superclass is optional.
+/// It is a variable expression, the superclass is looked up when the
+/// declaration runs.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ClassStatement<'a> {
+    pub name: Token<'a>,
+    pub superclass: Option<Expr<'a>>,
+    pub methods: Vec<Rc<FunctionStatement<'a>>>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl<'a> ClassStatement<'a> {
+    pub fn new(
+        name: Token<'a>,
+        superclass: Option<Expr<'a>>,
+        methods: Vec<Rc<FunctionStatement<'a>>>,
+        doc: Option<String>,
+        span: Span,
+    ) -> ClassStatement<'a> {
+        ClassStatement {
+            name,
+            superclass,
+            methods,
+            doc,
+            span,
+        }
+    }
+}
+
+/// The statements of a whole source file.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Program<'a> {
+    pub statements: Vec<Stmt<'a>>,
+}
+
+impl fmt::Display for Program<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for statement in &self.statements {
+            writeln!(f, "{}", statement)?;
+        }
+        Ok(())
+    }
+}
+
+/// A syntax error. `expected` is the token the grammar asked for, when it
+/// asked for a specific one, and `found` the token that was there instead.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub expected: Option<TokenType>,
+    pub found: TokenType,
+    pub lexeme: String,
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(expected: Option<TokenType>, found: &Token, message: &str) -> ParseError {
+        ParseError {
+            expected,
+            found: found.token_type,
+            lexeme: found.lexeme.to_string(),
+            message: message.to_string(),
+            span: found.span,
+        }
+    }
+
+    /// The error without its line, `Error at ';': Expect expression.`, for
+    /// callers that say where it is in their own way.
+    pub fn summary(&self) -> String {
+        if self.found == TokenType::Eof {
+            format!("Error at end: {}", self.message)
+        } else {
+            format!("Error at '{}': {}", self.lexeme, self.message)
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] {}", self.span.line, self.summary())
+    }
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+// The most arguments a call, and parameters a function, can have
+const MAX_ARGUMENTS: usize = 255;
+
+// What kind of function body the parser is in, for the rules about `return`
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+// What kind of class body the parser is in, for the rules about `this`
+// and `super`
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+struct Parser<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    statements: Vec<Stmt<'a>>,
+    errors: Vec<ParseError>,
     current: usize,
+    function: FunctionKind,
+    class: ClassKind,
 }
 
-impl Parser<'_> {
-    fn new(tokens: &Vec<Token>) -> Parser {
+impl<'t, 'a> Parser<'t, 'a> {
+    fn new(tokens: &'t [Token<'a>]) -> Parser<'t, 'a> {
         Parser {
             tokens,
-            expressions: Vec::new(),
+            statements: Vec::new(),
+            errors: Vec::new(),
             current: 0,
+            function: FunctionKind::None,
+            class: ClassKind::None,
         }
     }
 
     fn is_at_end(&self) -> bool {
-        self.current + 1 >= self.expressions.len()
+        self.peek_type_is(TokenType::Eof)
     }
 
-    fn previous(&self) -> Option<Token> {
+    fn previous(&self) -> Option<Token<'a>> {
         if self.current > 0 {
             Some(self.tokens[self.current - 1].clone())
         } else {
@@ -96,131 +700,608 @@ impl Parser<'_> {
     }
 
     // Consume current token and return it
-    fn advance(&mut self) -> Option<Token> {
+    fn advance(&mut self) -> Option<Token<'a>> {
         if !self.is_at_end() {
-            self.current = self.current + 1;
+            self.current = self.next_index() + 1;
         }
         self.previous()
     }
 
-    fn peek(&self) -> &Token {
-        &self.tokens[self.current]
+    fn peek(&self) -> &Token<'a> {
+        &self.tokens[self.next_index()]
     }
 
-    fn current_token(&self) -> Token {
-        self.tokens[self.current].clone()
+    fn current_token(&self) -> Token<'a> {
+        self.peek().clone()
     }
 
-    fn add_expression(&mut self, expr: Expr) {
-        self.expressions.push(expr);
+    // Doc comments only matter in front of declarations, which read them
+    // with `doc_comments`. Anywhere else they are skipped like comments.
+    fn next_index(&self) -> usize {
+        let mut index = self.current;
+        while self.tokens[index].token_type == TokenType::DocComment {
+            index += 1;
+        }
+        index
     }
 
-    fn add_primary_expression(&mut self, expr: LiteralExpression) {
-        self.add_expression(Expr::Literal(expr));
+    // declaration -> classDecl | funDecl | varDecl | statement
+    //
+    // Errors are recorded here, where parsing can pick up again at the next
+    // statement. `None` means there was nothing to keep.
+    fn declaration(&mut self) -> Option<Stmt<'a>> {
+        let doc = self.doc_comments();
+        let result = if self.match_tokens(vec![TokenType::Class]) {
+            self.class_declaration(doc)
+        } else if self.match_tokens(vec![TokenType::Fun]) {
+            let keyword = self.previous().unwrap();
+            self.function(keyword, FunctionKind::Function, doc)
+                .map(Stmt::Function)
+        } else if self.match_tokens(vec![TokenType::Var]) {
+            self.var_declaration(doc)
+        } else {
+            self.statement()
+        };
+        match result {
+            Ok(stmt) => Some(stmt),
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronize();
+                None
+            }
+        }
     }
 
-    fn add_variable_expression(&mut self, token: Token) {
-        self.add_primary_expression(LiteralExpression::Variable(token.lexeme))
+    // Consecutive `///` lines, joined into one text
+    fn doc_comments(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
+        while self.tokens[self.current].token_type == TokenType::DocComment {
+            lines.push(string_value(&self.tokens[self.current]));
+            self.current += 1;
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
     }
 
-    fn add_string_expression(&mut self, token: Token) {
-        self.add_primary_expression(LiteralExpression::String(token.lexeme));
+    // classDecl -> "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}"
+    fn class_declaration(&mut self, doc: Option<String>) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        let name = self.consume(TokenType::Identifier, "Expect class name.")?;
+        let superclass = if self.match_tokens(vec![TokenType::Less]) {
+            let superclass = self.consume(TokenType::Identifier, "Expect superclass name.")?;
+            if superclass.lexeme == name.lexeme {
+                let error = ParseError::new(None, &superclass, "A class can't inherit from itself.");
+                self.errors.push(error);
+            }
+            let variable = Literal::Variable(superclass.lexeme.to_string());
+            Some(Expr::Literal(LiteralExpression::new(variable, superclass.span)))
+        } else {
+            None
+        };
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+
+        let kind = if superclass.is_some() {
+            ClassKind::Subclass
+        } else {
+            ClassKind::Class
+        };
+        let enclosing = std::mem::replace(&mut self.class, kind);
+        let methods = self.methods();
+        self.class = enclosing;
+        let methods = methods?;
+
+        let right = self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+        let span = keyword.span.to(right.span);
+        Ok(Stmt::Class(ClassStatement::new(name, superclass, methods, doc, span)))
     }
 
-    fn add_number_expression(&mut self, token: Token) {
-        let number = match token.value {
-            Some(Value::Number(number)) => number,
-            _ => panic!("Token was supposed to contain number"),
+    fn methods(&mut self) -> ParseResult<Vec<Rc<FunctionStatement<'a>>>> {
+        let mut methods = Vec::new();
+        loop {
+            let doc = self.doc_comments();
+            if self.is_at_end() || self.peek_type_is(TokenType::RightBrace) {
+                return Ok(methods);
+            }
+            let name = self.peek().clone();
+            let kind = if name.lexeme == "init" {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+            methods.push(self.function(name, kind, doc)?);
+        }
+    }
+
+    // funDecl  -> "fun" function
+    // function -> IDENTIFIER "(" parameters? ")" block
+    //
+    // `start` is where the declaration starts, the `fun` keyword or, for
+    // methods, the name
+    fn function(
+        &mut self,
+        start: Token<'a>,
+        kind: FunctionKind,
+        doc: Option<String>,
+    ) -> ParseResult<Rc<FunctionStatement<'a>>> {
+        let expected = if kind == FunctionKind::Function {
+            "Expect function name."
+        } else {
+            "Expect method name."
         };
-        self.add_primary_expression(LiteralExpression::Number(number));
+        let name = self.consume(TokenType::Identifier, expected)?;
+        self.consume(TokenType::LeftParen, "Expect '(' after function name.")?;
+        let mut params = Vec::new();
+        if !self.peek_type_is(TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    let error = ParseError::new(None, self.peek(), "Can't have more than 255 parameters.");
+                    self.errors.push(error);
+                }
+                params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                if !self.match_tokens(vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
+
+        let enclosing = std::mem::replace(&mut self.function, kind);
+        let body = self.block_statements();
+        self.function = enclosing;
+        let (body, right) = body?;
+
+        let span = start.span.to(right.span);
+        Ok(Rc::new(FunctionStatement::new(name, params, body, doc, span)))
     }
 
-    fn add_unary_expression(&mut self, expr: UnaryExpression) {
-        self.add_expression(Expr::Unary(expr));
+    // varDecl -> "var" IDENTIFIER ( "=" expression )? ";"
+    fn var_declaration(&mut self, doc: Option<String>) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let initializer = if self.match_tokens(vec![TokenType::Equals]) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
+        let span = keyword.span.to(semicolon.span);
+        Ok(Stmt::Var(VarStatement::new(name, initializer, doc, span)))
     }
 
-    fn parse_expression(&mut self) -> Expr {
-        self.parse_equality()
+    // statement -> printStmt | returnStmt | ifStmt | whileStmt | forStmt
+    //              | block | exprStmt
+    fn statement(&mut self) -> ParseResult<Stmt<'a>> {
+        if self.match_tokens(vec![TokenType::Print]) {
+            return self.print_statement();
+        }
+        if self.match_tokens(vec![TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.match_tokens(vec![TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.match_tokens(vec![TokenType::While]) {
+            return self.while_statement();
+        }
+        if self.match_tokens(vec![TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.match_tokens(vec![TokenType::LeftBrace]) {
+            return self.block();
+        }
+        self.expression_statement()
     }
 
-    fn parse_equality(&mut self) -> Expr {
-        let expr = self.parse_term();
+    // printStmt -> "print" expression ";"
+    fn print_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        let expression = self.parse_expression()?;
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+        let span = keyword.span.to(semicolon.span);
+        Ok(Stmt::Print(PrintStatement::new(expression, span)))
+    }
 
-        while self.match_tokens(vec![TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual]) {
-            let operator = self.previous();
-            match operator {
-                Some(op) => {
-                    let right = self.parse_term();
-                    BinaryExpression::new(Box::new(expr.clone()), op.clone(), Box::new(right));
-                }
-                None => panic!("Operator is None but was supposed to be something")
+    // returnStmt -> "return" expression? ";"
+    fn return_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        // These are reported without giving up, what follows parses just fine
+        if self.function == FunctionKind::None {
+            let error = ParseError::new(None, &keyword, "Can't return from top-level code.");
+            self.errors.push(error);
+        }
+        let value = if self.peek_type_is(TokenType::Semicolon) {
+            None
+        } else {
+            if self.function == FunctionKind::Initializer {
+                let error = ParseError::new(None, &keyword, "Can't return a value from an initializer.");
+                self.errors.push(error);
             }
+            Some(self.parse_expression()?)
+        };
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        let span = keyword.span.to(semicolon.span);
+        Ok(Stmt::Return(ReturnStatement::new(keyword, value, span)))
+    }
 
+    // ifStmt -> "if" "(" expression ")" statement ( "else" statement )?
+    //
+    // An `else` binds to the nearest `if`, which is the one parsing the
+    // statement right before it
+    fn if_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if self.match_tokens(vec![TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        let end = else_branch.as_ref().unwrap_or(&then_branch).span();
+        let span = keyword.span.to(end);
+        Ok(Stmt::If(IfStatement::new(condition, then_branch, else_branch, span)))
+    }
+
+    // whileStmt -> "while" "(" expression ")" statement
+    fn while_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = Box::new(self.statement()?);
+        let span = keyword.span.to(body.span());
+        Ok(Stmt::While(WhileStatement::new(condition, body, span)))
+    }
+
+    // forStmt -> "for" "(" ( varDecl | exprStmt | ";" ) expression? ";"
+    //            expression? ")" statement
+    //
+    // Desugared into `{ initializer; while (condition) { body; increment; } }`
+    fn for_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let keyword = self.previous().unwrap();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let initializer = if self.match_tokens(vec![TokenType::Semicolon]) {
+            None
+        } else if self.match_tokens(vec![TokenType::Var]) {
+            Some(self.var_declaration(None)?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+        let condition = if self.peek_type_is(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+        let increment = if self.peek_type_is(TokenType::RightParen) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let mut body = self.statement()?;
+        let span = keyword.span.to(body.span());
+
+        if let Some(increment) = increment {
+            let increment_span = increment.span();
+            body = Stmt::Block(BlockStatement::new(
+                vec![
+                    body,
+                    Stmt::Expression(ExpressionStatement::new(increment, increment_span)),
+                ],
+                span,
+            ));
+        }
+        // A missing condition loops forever
+        let condition = condition.unwrap_or_else(|| {
+            Expr::Literal(LiteralExpression::new(Literal::Boolean(true), semicolon.span))
+        });
+        body = Stmt::While(WhileStatement::new(condition, Box::new(body), span));
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(BlockStatement::new(vec![initializer, body], span));
         }
+        Ok(body)
+    }
 
-        expr
+    // block -> "{" declaration* "}"
+    fn block(&mut self) -> ParseResult<Stmt<'a>> {
+        let left = self.previous().unwrap();
+        let (statements, right) = self.block_statements()?;
+        Ok(Stmt::Block(BlockStatement::new(statements, left.span.to(right.span))))
     }
 
-    fn parse_term(&mut self) -> Expr {
-        let expr = self.parse_factor();
+    // The rest of a block once its `{` was consumed, and the closing `}`
+    fn block_statements(&mut self) -> ParseResult<(Vec<Stmt<'a>>, Token<'a>)> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() && !self.peek_type_is(TokenType::RightBrace) {
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
+        }
+        let right = self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok((statements, right))
+    }
 
-        while self.match_tokens(vec![TokenType::Minus, TokenType::Plus]) {
-            let operator = self.previous();
-            match operator {
-                Some(op) => {
-                    let right = self.parse_factor();
-                    BinaryExpression::new(Box::new(expr.clone()), op.clone(), Box::new(right));
-                }
-                None => panic!("Operator is None but was supposed to be something")
+    // exprStmt -> expression ";"
+    fn expression_statement(&mut self) -> ParseResult<Stmt<'a>> {
+        let expression = self.parse_expression()?;
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
+        let span = expression.span().to(semicolon.span);
+        Ok(Stmt::Expression(ExpressionStatement::new(expression, span)))
+    }
+
+    // expression -> assignment
+    fn parse_expression(&mut self) -> ParseResult<Expr<'a>> {
+        self.parse_assignment()
+    }
+
+    // assignment -> ( call "." )? IDENTIFIER "=" assignment | logic_or
+    //
+    // The target is parsed as an ordinary expression, since there is no
+    // telling it apart from one before reaching the `=`
+    fn parse_assignment(&mut self) -> ParseResult<Expr<'a>> {
+        let expr = self.parse_or()?;
+        if !self.match_tokens(vec![TokenType::Equals]) {
+            return Ok(expr);
+        }
+        let equals = self.previous().unwrap();
+        let value = self.parse_assignment()?;
+        match expr {
+            Expr::Literal(LiteralExpression {
+                value: Literal::Variable(name),
+                span,
+                ..
+            }) => {
+                let span = span.to(value.span());
+                Ok(Expr::Assign(AssignExpression::new(name, Box::new(value), span)))
+            }
+            Expr::Get(get) => Ok(Expr::Set(SetExpression::new(get.object, get.name, Box::new(value)))),
+            // The parser is not lost, so there is no need to synchronize
+            _ => {
+                self.errors.push(ParseError::new(None, &equals, "Invalid assignment target."));
+                Ok(expr)
             }
+        }
+    }
 
+    // logic_or -> logic_and ( "or" logic_and )*
+    fn parse_or(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_and()?;
+        while self.match_tokens(vec![TokenType::Or]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_and()?;
+            expr = Expr::Logical(LogicalExpression::new(Box::new(expr), operator, Box::new(right)));
         }
+        Ok(expr)
+    }
 
-        expr
+    // logic_and -> equality ( "and" equality )*
+    fn parse_and(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_equality()?;
+        while self.match_tokens(vec![TokenType::And]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_equality()?;
+            expr = Expr::Logical(LogicalExpression::new(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
     }
 
-    fn parse_factor(&mut self) -> Expr {
-        let expr = self.parse_unary();
+    // equality -> comparison ( ( "!=" | "==" ) comparison )*
+    fn parse_equality(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_comparison()?;
+        while self.match_tokens(vec![TokenType::BangEquals, TokenType::EqualsEquals]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_comparison()?;
+            expr = Expr::Binary(BinaryExpression::new(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
+    }
 
+    // comparison -> term ( ( ">" | ">=" | "<" | "<=" ) term )*
+    fn parse_comparison(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_term()?;
+        while self.match_tokens(vec![
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+        ]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_term()?;
+            expr = Expr::Binary(BinaryExpression::new(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
+    }
+
+    // term -> factor ( ( "-" | "+" ) factor )*
+    fn parse_term(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_factor()?;
+        while self.match_tokens(vec![TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_factor()?;
+            expr = Expr::Binary(BinaryExpression::new(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
+    }
+
+    // factor -> unary ( ( "/" | "*" ) unary )*
+    fn parse_factor(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_unary()?;
         while self.match_tokens(vec![TokenType::Slash, TokenType::Star]) {
-            let operator = self.previous();
-            match operator {
-                Some(op) => {
-                    let right = self.parse_unary();
-                    BinaryExpression::new(Box::new(expr.clone()), op.clone(), Box::new(right));
+            let operator = self.previous().unwrap();
+            let right = self.parse_unary()?;
+            expr = Expr::Binary(BinaryExpression::new(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
+    }
+
+    // unary -> ( "!" | "-" ) unary | call
+    fn parse_unary(&mut self) -> ParseResult<Expr<'a>> {
+        if self.match_tokens(vec![TokenType::Bang, TokenType::Minus]) {
+            let operator = self.previous().unwrap();
+            let right = self.parse_unary()?;
+            return Ok(Expr::Unary(UnaryExpression::new(operator, Box::new(right))));
+        }
+        self.parse_call()
+    }
+
+    // call -> primary ( "(" arguments? ")" | "." IDENTIFIER )*
+    fn parse_call(&mut self) -> ParseResult<Expr<'a>> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.match_tokens(vec![TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_tokens(vec![TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get(GetExpression::new(Box::new(expr), name));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // arguments -> expression ( "," expression )*
+    fn finish_call(&mut self, callee: Expr<'a>) -> ParseResult<Expr<'a>> {
+        let mut arguments = Vec::new();
+        if !self.peek_type_is(TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    let error = ParseError::new(None, self.peek(), "Can't have more than 255 arguments.");
+                    self.errors.push(error);
+                }
+                arguments.push(self.parse_expression()?);
+                if !self.match_tokens(vec![TokenType::Comma]) {
+                    break;
                 }
-                None => panic!("Operator is None but was supposed to be something")
             }
         }
-        expr
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(Expr::Call(CallExpression::new(Box::new(callee), paren, arguments)))
     }
 
-    fn parse_unary(&mut self) -> Expr {
-        if self.match_tokens(vec![TokenType::Bang, TokenType::Minus]) {
-            let operator = self.previous();
-            match operator {
-                Some(op) => {
-                    let right = self.parse_unary();
-                    return Expr::Unary(UnaryExpression::new(op.clone(), Box::new(right)))
+    fn parse_primary(&mut self) -> ParseResult<Expr<'a>> {
+        let token = self.current_token();
+        let value = match token.token_type {
+            TokenType::True => Literal::Boolean(true),
+            TokenType::False => Literal::Boolean(false),
+            TokenType::Nil => Literal::Nil(String::from("nil")),
+            TokenType::Identifier => Literal::Variable(token.lexeme.to_string()),
+            TokenType::String => Literal::String(string_value(&token)),
+            TokenType::Number => match token.value {
+                Some(Value::Number(number)) => Literal::Number(number),
+                _ => panic!("Token was supposed to contain number"),
+            },
+            TokenType::Interpolation => return self.parse_interpolation(),
+            TokenType::LeftParen => return self.parse_grouping(),
+            TokenType::This => {
+                self.advance();
+                if self.class == ClassKind::None {
+                    let error = ParseError::new(None, &token, "Can't use 'this' outside of a class.");
+                    self.errors.push(error);
                 }
-                None => panic!("Operator is None but was supposed to be something")
+                return Ok(Expr::This(ThisExpression::new(token)));
             }
+            TokenType::Super => {
+                self.advance();
+                let message = match self.class {
+                    ClassKind::None => Some("Can't use 'super' outside of a class."),
+                    ClassKind::Class => Some("Can't use 'super' in a class with no superclass."),
+                    ClassKind::Subclass => None,
+                };
+                if let Some(message) = message {
+                    self.errors.push(ParseError::new(None, &token, message));
+                }
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+                return Ok(Expr::Super(SuperExpression::new(token, method)));
+            }
+            _ => return Err(ParseError::new(None, &token, "Expect expression.")),
+        };
+        self.advance();
+        Ok(Expr::Literal(LiteralExpression::new(value, token.span)))
+    }
 
+    // grouping -> "(" expression ")"
+    fn parse_grouping(&mut self) -> ParseResult<Expr<'a>> {
+        let left = self.current_token();
+        self.advance();
+        let expression = self.parse_expression()?;
+        let right = self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+        Ok(Expr::Grouping(GroupingExpression::new(
+            Box::new(expression),
+            left.span.to(right.span),
+        )))
+    }
 
+    // "a ${b} c" arrives as Interpolation(a ), the tokens of b, String( c)
+    fn parse_interpolation(&mut self) -> ParseResult<Expr<'a>> {
+        let start = self.current_token();
+        self.advance();
+        let mut parts = Vec::new();
+        push_interpolated_literal(&mut parts, &start);
+        loop {
+            let next = self.current_token();
+            if next.continues_string {
+                return Err(empty_interpolation_error(&next));
+            }
+            parts.push(InterpolationPart::Expr(self.parse_expression()?));
+            let segment = self.current_token();
+            match segment.token_type {
+                TokenType::Interpolation if segment.continues_string => {
+                    self.advance();
+                    push_interpolated_literal(&mut parts, &segment);
+                }
+                TokenType::String if segment.continues_string => {
+                    self.advance();
+                    push_interpolated_literal(&mut parts, &segment);
+                    let span = start.span.to(segment.span);
+                    return Ok(Expr::Interpolation(InterpolationExpression::new(parts, span)));
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        Some(TokenType::RightBrace),
+                        &segment,
+                        "Expect '}' after interpolated expression.",
+                    ))
+                }
+            }
         }
+    }
 
-        self.parse_primary()
+    // Consumes a token of the expected type or fails with `message`
+    fn consume(&mut self, token_type: TokenType, message: &str) -> ParseResult<Token<'a>> {
+        if self.peek_type_is(token_type) {
+            return Ok(self.advance().unwrap());
+        }
+        Err(ParseError::new(Some(token_type), self.peek(), message))
     }
 
-    fn parse_primary(&mut self) -> Expr {
-        match self.peek().token_type {
-            TokenType::True => Expr::Literal(LiteralExpression::Boolean(true)),
-            TokenType::False => Expr::Literal(LiteralExpression::Boolean(false)),
-            TokenType::Nil => Expr::Literal(LiteralExpression::Nil(String::from("nil"))),
-            TokenType::Identifier => Expr::Literal(LiteralExpression::Variable(String::from("nil"))),
-            TokenType::String => Expr::Literal(LiteralExpression::Nil(String::from("nil"))),
-            TokenType::Number => Expr::Literal(LiteralExpression::Nil(String::from("nil"))),
-            _ => todo!()
+    // After an error, skips tokens until the start of the next statement so
+    // the errors that follow are real ones and not fallout of the first
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().map(|token| token.token_type) == Some(TokenType::Semicolon) {
+                return;
+            }
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
         }
     }
 
@@ -237,25 +1318,53 @@ impl Parser<'_> {
         self.peek().token_type == token_type
     }
 
-    fn parse(&mut self) -> Vec<Expr> {
-        while self.current < self.tokens.len() {
-            let token = self.current_token();
-            match token.token_type {
-                TokenType::Identifier => self.add_variable_expression(token),
-                TokenType::String => self.add_string_expression(token),
-                TokenType::Number => self.add_number_expression(token),
-                TokenType::Bang => {
-                    self.parse_unary();
-                }
-                _ => panic!("Parser: Unrecognized token {:?}", token),
+    // program -> declaration* EOF
+    fn parse(&mut self) -> Result<Program<'a>, Vec<ParseError>> {
+        while !self.is_at_end() {
+            if let Some(stmt) = self.declaration() {
+                self.statements.push(stmt);
             }
-            self.current = self.current + 1;
         }
-        self.expressions.clone()
+        if self.errors.is_empty() {
+            Ok(Program {
+                statements: std::mem::take(&mut self.statements),
+            })
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+}
+
+fn string_value(token: &Token) -> String {
+    match &token.value {
+        Some(Value::String(string)) => string.clone(),
+        _ => panic!("Token was supposed to contain a string"),
+    }
+}
+
+// `${}` with nothing inside: the error is at the `}`, where the segment
+// that continues the string starts
+fn empty_interpolation_error(segment: &Token) -> ParseError {
+    let span = segment.span;
+    let brace = Span::new(span.file, span.start, span.start + 1, span.line, span.column);
+    ParseError::new(
+        None,
+        &Token::new(TokenType::RightBrace, "}", brace),
+        "Expect expression inside '${}'.",
+    )
+}
+
+fn push_interpolated_literal(parts: &mut Vec<InterpolationPart>, token: &Token) {
+    let string = string_value(token);
+    if !string.is_empty() {
+        parts.push(InterpolationPart::Literal(string));
     }
 }
 
-pub fn parse(tokens: &Vec<Token>) -> Vec<Expr> {
+/// Parses the tokens of a program, which must end with an `Eof` token as
+/// produced by the scanner. Parsing goes on after an error, so all the
+/// syntax errors of the program are reported at once.
+pub fn parse<'a>(tokens: &[Token<'a>]) -> Result<Program<'a>, Vec<ParseError>> {
     let mut parser = Parser::new(tokens);
     parser.parse()
 }
@@ -1,52 +1,627 @@
-use crate::parser::{parse, Expr, LiteralExpression, UnaryExpression};
+use crate::parser::{
+    parse, Expr, InterpolationPart, Literal, LiteralExpression, ParseError, Stmt,
+    UnaryExpression,
+};
 use crate::scanner::scan_program;
+use crate::span::{FileId, Span};
+use crate::token::{Token, TokenType};
+
+// The expressions of a program made of expression statements
+fn expressions<'a>(tokens: &[Token<'a>]) -> Vec<Expr<'a>> {
+    parse(tokens)
+        .unwrap()
+        .statements
+        .into_iter()
+        .map(|stmt| match stmt {
+            Stmt::Expression(stmt) => stmt.expression,
+            stmt => panic!("{:?} is not an expression statement", stmt),
+        })
+        .collect()
+}
+
+fn literal(value: Literal, start: usize, end: usize) -> Expr<'static> {
+    Expr::Literal(LiteralExpression::new(
+        value,
+        Span::new(FileId::default(), start, end, 1, start + 1),
+    ))
+}
 
 #[test]
 fn parse_an_identifier() {
-    let tokens = scan_program("somevar");
-    assert_eq!(tokens.len(), 1);
-    let expressions = parse(&tokens);
+    let (tokens, _) = scan_program("somevar;");
+    assert_eq!(tokens.len(), 3);
+    let expressions = expressions(&tokens);
     assert_eq!(expressions.len(), 1);
     assert_eq!(
         expressions[0],
-        Expr::Literal(LiteralExpression::Variable(String::from("somevar")))
+        literal(Literal::Variable(String::from("somevar")), 0, 7)
     );
 }
 
 #[test]
 fn parse_a_number() {
-    let tokens = scan_program("432");
-    assert_eq!(tokens.len(), 1);
-    let expressions = parse(&tokens);
+    let (tokens, _) = scan_program("432;");
+    assert_eq!(tokens.len(), 3);
+    let expressions = expressions(&tokens);
     assert_eq!(expressions.len(), 1);
     assert_eq!(
         expressions[0],
-        Expr::Literal(LiteralExpression::Number(432.0))
+        literal(Literal::Number(432.0), 0, 3)
     );
 }
 
 #[test]
 fn parse_a_string() {
-    let tokens = scan_program("\"thing\"");
-    assert_eq!(tokens.len(), 1);
-    let expressions = parse(&tokens);
+    let (tokens, _) = scan_program("\"thing\";");
+    assert_eq!(tokens.len(), 3);
+    let expressions = expressions(&tokens);
     assert_eq!(expressions.len(), 1);
     assert_eq!(
         expressions[0],
-        Expr::Literal(LiteralExpression::String(String::from("thing")))
+        literal(Literal::String(String::from("thing")), 0, 7)
     );
 }
 
 #[test]
 fn parse_simple_negation() {
-    let tokens = scan_program("!somevar");
-    assert_eq!(tokens.len(), 2);
-    let expressions = parse(&tokens);
-    assert_eq!(expressions.len(), 2);
+    let (tokens, _) = scan_program("!somevar;");
+    assert_eq!(tokens.len(), 4);
+    let expressions = expressions(&tokens);
+    assert_eq!(expressions.len(), 1);
     assert_eq!(
         expressions[0],
         Expr::Unary(UnaryExpression::new(tokens[0].clone(), Box::new(
-            Expr::Literal(LiteralExpression::Variable(String::from("somevar"))
+            literal(Literal::Variable(String::from("somevar")), 1, 8)
         )))
-    ));
-}
\ No newline at end of file
+    );
+}
+
+#[test]
+fn literal_spans_point_at_their_token() {
+    let (tokens, _) = scan_program("  somevar;");
+    let expressions = expressions(&tokens);
+    assert_eq!(
+        Span::new(FileId::default(), 2, 9, 1, 3),
+        expressions[0].span()
+    );
+}
+
+fn parse_errors(source: &str) -> Vec<ParseError> {
+    let (tokens, _) = scan_program(source);
+    parse(&tokens).unwrap_err()
+}
+
+#[test]
+fn missing_operand_is_an_error_at_end() {
+    let errors = parse_errors("!");
+    assert_eq!(1, errors.len());
+    assert_eq!(None, errors[0].expected);
+    assert_eq!(TokenType::Eof, errors[0].found);
+    assert_eq!("[line 1] Error at end: Expect expression.", errors[0].to_string());
+}
+
+#[test]
+fn parse_string_with_escapes_uses_its_value() {
+    let (tokens, _) = scan_program(r#""a\tb";"#);
+    let expressions = expressions(&tokens);
+    assert_eq!(literal(Literal::String(String::from("a\tb")), 0, 6), expressions[0]);
+}
+
+#[test]
+fn parse_string_interpolation() {
+    let (tokens, _) = scan_program(r#""Hello ${name}, you are ${-age}";"#);
+    let expressions = expressions(&tokens);
+    assert_eq!(expressions.len(), 1);
+    let interpolation = match &expressions[0] {
+        Expr::Interpolation(interpolation) => interpolation,
+        expr => panic!("{:?} is not an interpolation", expr),
+    };
+    assert_eq!(
+        vec![
+            InterpolationPart::Literal(String::from("Hello ")),
+            InterpolationPart::Expr(literal(Literal::Variable(String::from("name")), 9, 13)),
+            InterpolationPart::Literal(String::from(", you are ")),
+            InterpolationPart::Expr(Expr::Unary(UnaryExpression::new(
+                tokens[3].clone(),
+                Box::new(literal(Literal::Variable(String::from("age")), 27, 30))
+            ))),
+        ],
+        interpolation.parts
+    );
+    assert_eq!((0, 32), (interpolation.span.start, interpolation.span.end));
+}
+
+#[test]
+fn unterminated_interpolation_is_an_error() {
+    let errors = parse_errors(r#""Hello ${name"#);
+    assert_eq!(1, errors.len());
+    assert_eq!(Some(TokenType::RightBrace), errors[0].expected);
+    assert_eq!("Expect '}' after interpolated expression.", errors[0].message);
+}
+
+#[test]
+fn strings_after_an_interpolated_expression_do_not_close_it() {
+    for source in [r#"print "a ${1 "b"} c";"#, r#"print "a ${1 "b ${2} d"} c";"#] {
+        let errors = parse_errors(source);
+        assert_eq!(1, errors.len());
+        assert_eq!("Expect '}' after interpolated expression.", errors[0].message);
+        // At the string after `1`
+        assert_eq!(14, errors[0].span.column);
+    }
+}
+
+#[test]
+fn empty_interpolation_is_an_error() {
+    let errors = parse_errors(r#"print "a ${} b";"#);
+    assert_eq!(1, errors.len());
+    assert_eq!("Error at '}': Expect expression inside '${}'.", errors[0].summary());
+    assert_eq!((11, 12), (errors[0].span.start, errors[0].span.end));
+    assert_eq!(1, parse_errors(r#"print "${ }";"#).len());
+    // A string as the whole expression is not an empty interpolation
+    assert_eq!("(interpolate \"a \" \"\" \"c\")", parse_to_string(r#""a ${""}c""#));
+}
+
+fn parse_to_string(source: &str) -> String {
+    let source = format!("{};", source);
+    let (tokens, _) = scan_program(&source);
+    let expressions = expressions(&tokens);
+    assert_eq!(1, expressions.len());
+    expressions[0].to_string()
+}
+
+#[test]
+fn binary_operators_follow_precedence() {
+    assert_eq!("(+ 1 (* 2 3))", parse_to_string("1 + 2 * 3"));
+    assert_eq!("(== (< a b) (>= c d))", parse_to_string("a < b == c >= d"));
+    assert_eq!("(!= (- 1) (/ (! x) 2))", parse_to_string("-1 != !x / 2"));
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!("(- (- 1 2) 3)", parse_to_string("1 - 2 - 3"));
+    assert_eq!("(/ (* a b) c)", parse_to_string("a * b / c"));
+    assert_eq!("(== (== a b) c)", parse_to_string("a == b == c"));
+}
+
+#[test]
+fn groupings_override_precedence() {
+    assert_eq!("(* (group (+ 1 2)) 3)", parse_to_string("(1 + 2) * 3"));
+    assert_eq!("(- (group (group x)))", parse_to_string("-((x))"));
+}
+
+#[test]
+fn literals_print_as_written() {
+    assert_eq!(
+        "(interpolate \"a \" (+ b 1) \"!\")",
+        parse_to_string(r#""a ${b + 1}!""#)
+    );
+    assert_eq!("(== (== true nil) \"s\")", parse_to_string(r#"true == nil == "s""#));
+}
+
+#[test]
+fn binary_spans_cover_both_operands() {
+    let (tokens, _) = scan_program("(1 + 2) * 3;");
+    let expressions = expressions(&tokens);
+    let (left, right) = match &expressions[0] {
+        Expr::Binary(binary) => (binary.left.span(), binary.right.span()),
+        expr => panic!("{:?} is not a binary expression", expr),
+    };
+    assert_eq!((0, 7), (left.start, left.end));
+    assert_eq!((10, 11), (right.start, right.end));
+    assert_eq!((0, 11), (expressions[0].span().start, expressions[0].span().end));
+}
+
+#[test]
+fn unclosed_grouping_is_an_error() {
+    let errors = parse_errors("(1 + 2");
+    assert_eq!(1, errors.len());
+    assert_eq!(Some(TokenType::RightParen), errors[0].expected);
+    assert_eq!(TokenType::Eof, errors[0].found);
+    assert_eq!("[line 1] Error at end: Expect ')' after expression.", errors[0].to_string());
+}
+
+#[test]
+fn errors_point_at_the_unexpected_token() {
+    let errors = parse_errors("1 +\n  * 2");
+    assert_eq!(1, errors.len());
+    assert_eq!(TokenType::Star, errors[0].found);
+    assert_eq!("*", errors[0].lexeme);
+    assert_eq!((2, 3), (errors[0].span.line, errors[0].span.column));
+    assert_eq!("[line 2] Error at '*': Expect expression.", errors[0].to_string());
+}
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let errors = parse_errors("(1 + ; 2 * ) ; (3 ; 4");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 1] Error at ';': Expect expression.",
+            "[line 1] Error at ')': Expect expression.",
+            "[line 1] Error at ';': Expect ')' after expression.",
+            "[line 1] Error at end: Expect ';' after expression.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn recovery_stops_before_statement_keywords() {
+    let errors = parse_errors(") + var ( print");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 1] Error at ')': Expect expression.",
+            "[line 1] Error at '(': Expect variable name.",
+            "[line 1] Error at end: Expect expression.",
+        ],
+        &messages[..]
+    );
+}
+
+fn program_to_string(source: &str) -> String {
+    let (tokens, _) = scan_program(source);
+    parse(&tokens).unwrap().to_string()
+}
+
+#[test]
+fn parse_statements() {
+    assert_eq!(
+        "(var a (+ 1 2))\n(var b)\n(print (* a 3))\n(; b)\n",
+        program_to_string("var a = 1 + 2;\nvar b;\nprint a * 3;\nb;")
+    );
+}
+
+#[test]
+fn parse_nested_blocks() {
+    assert_eq!(
+        "(block (var a 1) (block (print a)) (block))\n",
+        program_to_string("{ var a = 1; { print a; } {} }")
+    );
+}
+
+#[test]
+fn empty_program_has_no_statements() {
+    assert_eq!(0, parse(&scan_program("").0).unwrap().statements.len());
+}
+
+#[test]
+fn statement_spans_include_the_semicolon() {
+    let (tokens, _) = scan_program("  print 1 ;\n{ x; }");
+    let program = parse(&tokens).unwrap();
+    let spans: Vec<(usize, usize)> = program
+        .statements
+        .iter()
+        .map(|stmt| (stmt.span().start, stmt.span().end))
+        .collect();
+    assert_eq!([(2, 11), (12, 18)], &spans[..]);
+}
+
+#[test]
+fn doc_comments_attach_to_var_declarations() {
+    let (tokens, _) = scan_program("/// The answer.\n/// Always.\nvar answer = 42;\n/// Lost.\nprint 1;\nvar plain;");
+    let program = parse(&tokens).unwrap();
+    let docs: Vec<Option<&str>> = program
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Var(var) => Some(var.doc.as_deref()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!([Some("The answer.\nAlways."), None], &docs[..]);
+}
+
+#[test]
+fn doc_comments_of_nothing_are_ignored() {
+    assert_eq!("(block)\n", program_to_string("{ /// inside\n}\n/// at the end"));
+}
+
+#[test]
+fn doc_comments_elsewhere_are_skipped() {
+    assert_eq!(
+        "(if x (print 1) (print 2))\n",
+        program_to_string("if (x)\n  /// note\n  print 1;\nelse /// other\n print 2;")
+    );
+    assert_eq!("(print (+ x 2))\n", program_to_string("print x +\n/// doc\n 2;"));
+    assert_eq!(
+        "(; (call f a b))\n",
+        program_to_string("f(a, /// first\n b /// second\n);")
+    );
+}
+
+#[test]
+fn statement_errors_recover_at_the_next_statement() {
+    let errors = parse_errors("print 1\nvar = 2;\n{ print ; var ok = 3; \nprint 4;");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            // Recovery skips the token the error was found at, so the
+            // broken declaration goes unreported
+            "[line 2] Error at 'var': Expect ';' after value.",
+            "[line 3] Error at ';': Expect expression.",
+            "[line 4] Error at end: Expect '}' after block.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn parse_if_else() {
+    assert_eq!(
+        "(if (< a 1) (print a) (block (print b)))\n(if c (; d))\n",
+        program_to_string("if (a < 1) print a; else { print b; }\nif (c) d;")
+    );
+}
+
+#[test]
+fn else_binds_to_the_nearest_if() {
+    assert_eq!(
+        "(if a (if b (print 1) (print 2)))\n",
+        program_to_string("if (a) if (b) print 1; else print 2;")
+    );
+}
+
+#[test]
+fn parse_while() {
+    assert_eq!(
+        "(while (< i 3) (block (print i)))\n",
+        program_to_string("while (i < 3) { print i; }")
+    );
+}
+
+#[test]
+fn for_loops_desugar_to_while() {
+    assert_eq!(
+        "(block (var i 0) (while (< i 3) (block (print i) (; (+ i 1)))))\n",
+        program_to_string("for (var i = 0; i < 3; i + 1) print i;")
+    );
+    assert_eq!("(while true (print 1))\n", program_to_string("for (;;) print 1;"));
+    assert_eq!(
+        "(block (; i) (while false (block (; j))))\n",
+        program_to_string("for (i; false;) { j; }")
+    );
+}
+
+#[test]
+fn control_flow_errors() {
+    let messages = |source| -> Vec<String> {
+        parse_errors(source)
+            .iter()
+            .map(|error| error.message.clone())
+            .collect()
+    };
+    assert_eq!(["Expect '(' after 'if'."], &messages("if a) print 1;")[..]);
+    assert_eq!(["Expect ')' after condition."], &messages("while (a print 1;")[..]);
+    assert_eq!(["Expect ';' after loop condition."], &messages("for (;a) print 1;")[..]);
+    assert_eq!(["Expect ')' after for clauses."], &messages("for (;;a print 1;")[..]);
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!("(or a (and b c))", parse_to_string("a or b and c"));
+    assert_eq!("(or (and a b) c)", parse_to_string("a and b or c"));
+    assert_eq!("(or (or a b) c)", parse_to_string("a or b or c"));
+}
+
+#[test]
+fn logical_operators_bind_looser_than_equality() {
+    assert_eq!("(and (== a 1) (!= b 2))", parse_to_string("a == 1 and b != 2"));
+}
+
+#[test]
+fn assignment_is_right_associative() {
+    assert_eq!("(= a (= b (+ c 1)))", parse_to_string("a = b = c + 1"));
+    assert_eq!("(= a (or b c))", parse_to_string("a = b or c"));
+}
+
+#[test]
+fn invalid_assignment_targets_are_reported_at_the_equals() {
+    for source in ["(a) = 3;", "1 = 2;", "a + b = c;", "-a = 1;"] {
+        let errors = parse_errors(source);
+        assert_eq!(1, errors.len(), "{}", source);
+        assert_eq!("Invalid assignment target.", errors[0].message);
+        assert_eq!(TokenType::Equals, errors[0].found);
+    }
+    let errors = parse_errors("var x;\n1 = 2; print 3;\n(x) = 4;");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 2] Error at '=': Invalid assignment target.",
+            "[line 3] Error at '=': Invalid assignment target.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn parse_calls() {
+    assert_eq!("(call f)", parse_to_string("f()"));
+    assert_eq!("(call (call f 1 (+ 2 3)) a)", parse_to_string("f(1, 2 + 3)(a)"));
+    assert_eq!("(- (call f))", parse_to_string("-f()"));
+}
+
+#[test]
+fn parse_function_declarations() {
+    assert_eq!(
+        "(fun add (a b) (return (+ a b)))\n(fun noop ())\n(print (call add 1 2))\n",
+        program_to_string("fun add(a, b) { return a + b; }\nfun noop() {}\nprint add(1, 2);")
+    );
+    assert_eq!(
+        "(fun f () (if x (return)) (block (return 1)))\n",
+        program_to_string("fun f() { if (x) return; { return 1; } }")
+    );
+}
+
+#[test]
+fn doc_comments_attach_to_functions() {
+    let (tokens, _) = scan_program("/// Adds.\nfun add(a, b) { return a + b; }");
+    let program = parse(&tokens).unwrap();
+    match &program.statements[0] {
+        Stmt::Function(function) => assert_eq!(Some("Adds."), function.doc.as_deref()),
+        stmt => panic!("{:?} is not a function", stmt),
+    }
+}
+
+#[test]
+fn return_outside_a_function_is_an_error() {
+    let errors = parse_errors("return 1;\nfun f() { return 2; }\n{ return; }");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 1] Error at 'return': Can't return from top-level code.",
+            "[line 3] Error at 'return': Can't return from top-level code.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn calls_and_functions_are_limited_to_255_arguments() {
+    let names: Vec<String> = (0..256).map(|index| format!("a{}", index)).collect();
+    let arguments = names.join(", ");
+    let at_limit = format!("f({});", names[..255].join(", "));
+    assert!(parse(&scan_program(&at_limit).0).is_ok());
+    let errors = parse_errors(&format!("f({});", arguments));
+    assert_eq!(1, errors.len());
+    assert_eq!("Can't have more than 255 arguments.", errors[0].message);
+    assert_eq!("a255", errors[0].lexeme);
+
+    let errors = parse_errors(&format!("fun f({}) {{}}", arguments));
+    assert_eq!(1, errors.len());
+    assert_eq!("Can't have more than 255 parameters.", errors[0].message);
+}
+
+#[test]
+fn function_declaration_errors() {
+    let messages = |source| -> Vec<String> {
+        parse_errors(source)
+            .iter()
+            .map(|error| error.message.clone())
+            .collect()
+    };
+    assert_eq!(["Expect function name."], &messages("fun (a) {}")[..]);
+    assert_eq!(["Expect parameter name."], &messages("fun f(a, 1) {}")[..]);
+    assert_eq!(["Expect '{' before function body."], &messages("fun f() return 1;")[..]);
+    assert_eq!(["Expect ')' after arguments."], &messages("f(1, 2;")[..]);
+}
+
+#[test]
+fn parse_class_declarations() {
+    assert_eq!(
+        "(class Point (fun init (x y) (; (set this x x)) (; (set this y y))) (fun sum () (return (+ (get this x) (get this y)))))\n(class Empty)\n",
+        program_to_string(
+            "class Point { init(x, y) { this.x = x; this.y = y; } sum() { return this.x + this.y; } }\nclass Empty {}"
+        )
+    );
+}
+
+#[test]
+fn parse_property_access() {
+    assert_eq!("(call (get (call (get a b)) c) 1)", parse_to_string("a.b().c(1)"));
+    assert_eq!("(set (get a b) c (= d 1))", parse_to_string("a.b.c = d = 1"));
+    assert_eq!("(set (call f) x 1)", parse_to_string("f().x = 1"));
+}
+
+#[test]
+fn doc_comments_attach_to_classes_and_methods() {
+    let (tokens, _) = scan_program("/// A thing.\nclass Thing {\n  /// Does it.\n  run() {}\n  /// Dangling.\n}");
+    let program = parse(&tokens).unwrap();
+    match &program.statements[0] {
+        Stmt::Class(class) => {
+            assert_eq!(Some("A thing."), class.doc.as_deref());
+            assert_eq!(1, class.methods.len());
+            assert_eq!(Some("Does it."), class.methods[0].doc.as_deref());
+        }
+        stmt => panic!("{:?} is not a class", stmt),
+    }
+}
+
+#[test]
+fn this_is_only_allowed_in_classes() {
+    let errors = parse_errors("print this;\nfun f() { return this; }\nclass A { m() { fun g() { return this; } } }");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 1] Error at 'this': Can't use 'this' outside of a class.",
+            "[line 2] Error at 'this': Can't use 'this' outside of a class.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn initializers_cannot_return_values() {
+    let errors = parse_errors("class A { init() { return 1; } }");
+    assert_eq!(1, errors.len());
+    assert_eq!("[line 1] Error at 'return': Can't return a value from an initializer.", errors[0].to_string());
+    // A bare return is fine, and so is returning from a function inside it
+    assert!(parse(&scan_program("class A { init() { return; fun f() { return 1; } } }").0).is_ok());
+}
+
+#[test]
+fn class_declaration_errors() {
+    let messages = |source| -> Vec<String> {
+        parse_errors(source)
+            .iter()
+            .map(|error| error.message.clone())
+            .collect()
+    };
+    assert_eq!(["Expect class name."], &messages("class {}")[..]);
+    assert_eq!(["Expect '{' before class body."], &messages("class A ()")[..]);
+    assert_eq!(["Expect method name."], &messages("class A { fun m() {} }")[..]);
+    assert_eq!(["Expect '}' after class body."], &messages("class A { m() {}")[..]);
+    assert_eq!(["Expect property name after '.'."], &messages("a.1;")[..]);
+}
+
+#[test]
+fn parse_subclasses_and_super() {
+    assert_eq!(
+        "(class B < A (fun m () (return (call (super m) 1))))\n",
+        program_to_string("class B < A { m() { return super.m(1); } }")
+    );
+}
+
+#[test]
+fn a_class_cannot_inherit_from_itself() {
+    let errors = parse_errors("class A < A {}");
+    assert_eq!(1, errors.len());
+    assert_eq!("[line 1] Error at 'A': A class can't inherit from itself.", errors[0].to_string());
+    assert_eq!(10, errors[0].span.start);
+}
+
+#[test]
+fn super_is_only_allowed_in_subclasses() {
+    let errors = parse_errors("super.m();\nclass A { m() { super.m(); } }\nclass B < A { m() { super.m(); } }");
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        [
+            "[line 1] Error at 'super': Can't use 'super' outside of a class.",
+            "[line 2] Error at 'super': Can't use 'super' in a class with no superclass.",
+        ],
+        &messages[..]
+    );
+}
+
+#[test]
+fn super_needs_a_method_name() {
+    let messages = |source| -> Vec<String> {
+        parse_errors(source)
+            .iter()
+            .map(|error| error.message.clone())
+            .collect()
+    };
+    assert_eq!(["Expect '.' after 'super'."], &messages("class B < A { m() { super; } }")[..]);
+    assert_eq!(["Expect superclass method name."], &messages("class B < A { m() { super.; } }")[..]);
+    assert_eq!(["Expect superclass name."], &messages("class B < {}")[..]);
+}
+
+#[test]
+fn error_summaries_leave_out_the_line() {
+    let errors = parse_errors("\n(1;\n(");
+    let summaries: Vec<String> = errors.iter().map(|error| error.summary()).collect();
+    assert_eq!(
+        ["Error at ';': Expect ')' after expression.", "Error at end: Expect expression."],
+        &summaries[..]
+    );
+}
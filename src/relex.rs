@@ -0,0 +1,152 @@
+//! Incremental re-lexing for editors: after a text edit only the region
+//! around it is scanned again, and the tokens before and after it are
+//! reused with their positions shifted.
+
+use std::ops::Range;
+
+use crate::scanner::{ScanError, Scanner};
+use crate::span::Span;
+use crate::token::{Token, TokenType};
+
+/// Replaces the bytes in `range` of the old source with `replacement`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit<'e> {
+    pub range: Range<usize>,
+    pub replacement: &'e str,
+}
+
+impl<'e> TextEdit<'e> {
+    pub fn new(range: Range<usize>, replacement: &'e str) -> TextEdit<'e> {
+        TextEdit { range, replacement }
+    }
+
+    /// Applies the edit to `source`.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = String::with_capacity(source.len() + self.replacement.len());
+        edited.push_str(&source[..self.range.start]);
+        edited.push_str(self.replacement);
+        edited.push_str(&source[self.range.end..]);
+        edited
+    }
+
+    // How much the text after the edit moves
+    fn delta(&self) -> isize {
+        self.replacement.len() as isize - self.range.len() as isize
+    }
+}
+
+// How far past its end the scanner may look to decide where a token ends,
+// as in `1e+5` where `1` is only a whole token if `e+5` doesn't follow
+const LOOKAHEAD: usize = 3;
+
+/// Updates `old_tokens`, the result of scanning `old_source`, for `edit`.
+/// `new_source` must be `old_source` with the edit applied. The tokens are
+/// the same a full `scan_program` of `new_source` would produce. The errors
+/// are only those found in the region that was scanned again.
+pub fn relex<'a>(
+    old_source: &str,
+    old_tokens: &[Token],
+    new_source: &'a str,
+    edit: &TextEdit,
+) -> (Vec<Token<'a>>, Vec<ScanError>) {
+    debug_assert_eq!(
+        new_source.len() as isize,
+        old_source.len() as isize + edit.delta()
+    );
+    // Restart from a token that the edit cannot have changed, one more to be
+    // safe about lookahead, and outside of any interpolation
+    let mut restart = old_tokens
+        .iter()
+        .position(|token| token.span.end + LOOKAHEAD >= edit.range.start)
+        .unwrap_or(old_tokens.len() - 1)
+        .saturating_sub(1);
+    while old_tokens[restart].in_interpolation {
+        restart -= 1;
+    }
+
+    // Tokens before the restart point keep their position
+    let mut tokens: Vec<Token<'a>> = old_tokens[..restart]
+        .iter()
+        .map(|token| moved(old_source, token, new_source, 0, 0, 0))
+        .collect();
+    let mut errors = Vec::new();
+
+    // The edit may come before the first token, so that one restarts from
+    // the top of the file
+    let from = &old_tokens[restart].span;
+    let mut scanner = if restart == 0 {
+        Scanner::resume(new_source, from.file, 0, 1, 1)
+    } else {
+        Scanner::resume(new_source, from.file, from.start, from.line, from.column)
+    };
+    let edit_end = edit.range.start + edit.replacement.len();
+    let mut before_in_interpolation = false;
+    while let Some(scanned) = scanner.next() {
+        let token = match scanned {
+            Ok(token) => token,
+            Err(error) => {
+                errors.push(error);
+                before_in_interpolation = scanner.in_interpolation();
+                continue;
+            }
+        };
+
+        if token.span.start >= edit_end && !before_in_interpolation {
+            // Past the edit the source is the same as before, so once a token
+            // starts where an old one did, in the same state, the rest of the
+            // old tokens follow unchanged
+            let old_start = (token.span.start as isize - edit.delta()) as usize;
+            if let Ok(index) = old_tokens.binary_search_by_key(&old_start, |old| old.span.start) {
+                if !old_tokens[index].in_interpolation {
+                    let old = &old_tokens[index];
+                    let lines = token.line as isize - old.line as isize;
+                    let columns = token.span.column as isize - old.span.column as isize;
+                    tokens.extend(old_tokens[index..].iter().map(|following| {
+                        // Only the rest of the synchronizing line moves sideways
+                        let columns = if following.line == old.line { columns } else { 0 };
+                        moved(old_source, following, new_source, edit.delta(), lines, columns)
+                    }));
+                    return (tokens, errors);
+                }
+            }
+        }
+
+        let is_eof = token.token_type == TokenType::Eof;
+        tokens.push(token);
+        if is_eof {
+            break;
+        }
+        before_in_interpolation = scanner.in_interpolation();
+    }
+    (tokens, errors)
+}
+
+// Moves a token of the old source to its place in the new one
+fn moved<'a>(
+    old_source: &str,
+    token: &Token,
+    new_source: &'a str,
+    delta: isize,
+    lines: isize,
+    columns: isize,
+) -> Token<'a> {
+    let shift = |offset: usize, by: isize| (offset as isize + by) as usize;
+    let lexeme_start = token.lexeme.as_ptr() as usize - old_source.as_ptr() as usize;
+    let lexeme_start = shift(lexeme_start, delta);
+    let span = Span::new(
+        token.span.file,
+        shift(token.span.start, delta),
+        shift(token.span.end, delta),
+        shift(token.span.line, lines),
+        shift(token.span.column, columns),
+    );
+    Token {
+        token_type: token.token_type,
+        lexeme: &new_source[lexeme_start..lexeme_start + token.lexeme.len()],
+        value: token.value.clone(),
+        line: shift(token.line, lines),
+        span,
+        continues_string: token.continues_string,
+        in_interpolation: token.in_interpolation,
+    }
+}
@@ -0,0 +1,134 @@
+use crate::relex::{relex, TextEdit};
+use crate::scanner::scan_program;
+use crate::token::TokenType;
+
+// Applies `edit` to `source` and checks that re-lexing gives the same tokens
+// as scanning the edited source from scratch
+fn check_edit(source: &str, edit: TextEdit) {
+    let (old_tokens, _) = scan_program(source);
+    let edited = edit.apply(source);
+    let (expected, _) = scan_program(&edited);
+    let (tokens, _) = relex(source, &old_tokens, &edited, &edit);
+    assert_eq!(
+        expected, tokens,
+        "\nsource: {:?}\nedit: {:?}\nedited: {:?}",
+        source, edit, edited
+    );
+    for (expected, token) in expected.iter().zip(&tokens) {
+        assert_eq!(expected.span, token.span, "in {:?}", edited);
+        assert_eq!(expected.lexeme.as_ptr(), token.lexeme.as_ptr());
+    }
+}
+
+#[test]
+fn edit_inside_an_identifier() {
+    check_edit("var answer = 42;\nprint answer;", TextEdit::new(5..7, "SW"));
+}
+
+#[test]
+fn insertion_shifts_following_lines_and_columns() {
+    let source = "var a = 1;\nvar b = 2; var c = 3;\nprint a;";
+    check_edit(source, TextEdit::new(15..15, "bb"));
+    check_edit(source, TextEdit::new(9..10, ";\nvar z = 0;\n"));
+}
+
+#[test]
+fn edit_that_extends_a_number() {
+    check_edit("3.x + 1", TextEdit::new(2..3, "5"));
+    check_edit("1 e+5", TextEdit::new(1..2, ""));
+}
+
+#[test]
+fn opening_a_block_comment_swallows_the_rest() {
+    let source = "a; b; /* c */ d;";
+    check_edit(source, TextEdit::new(2..2, "/*"));
+    check_edit(source, TextEdit::new(11..13, ""));
+}
+
+#[test]
+fn editing_a_string_and_its_interpolation() {
+    let source = r#"print "a ${x + {}} b ${"c ${d}"} e"; f;"#;
+    check_edit(source, TextEdit::new(12..13, "yy"));
+    check_edit(source, TextEdit::new(6..7, ""));
+    check_edit(source, TextEdit::new(16..17, ""));
+    check_edit(source, TextEdit::new(28..28, "}"));
+}
+
+#[test]
+fn edit_at_the_very_end_and_start() {
+    let source = "print 1;";
+    check_edit(source, TextEdit::new(8..8, " print 2;"));
+    check_edit(source, TextEdit::new(0..0, "// hi\n"));
+    check_edit(source, TextEdit::new(0..8, ""));
+}
+
+#[test]
+fn only_the_edited_region_is_scanned() {
+    let source = "var a = 1;\n".repeat(1000);
+    let (old_tokens, _) = scan_program(&source);
+    let edit = TextEdit::new(5000..5000, "\"oops\" @");
+    let edited = edit.apply(&source);
+    let (tokens, errors) = relex(&source, &old_tokens, &edited, &edit);
+    assert_eq!(old_tokens.len() + 1, tokens.len());
+    assert_eq!(TokenType::Eof, tokens.last().unwrap().token_type);
+    // Errors before or after the edit are not reported again
+    assert_eq!(1, errors.len());
+    assert_eq!("@", errors[0].text);
+}
+
+// A small xorshift generator so the randomized test is reproducible
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.next() % n
+    }
+}
+
+const PROGRAM: &str = r#"
+/// Says hello.
+fun greet(name) {
+    print "Hello ${name}, you are ${age + {}} years old\n";
+    /* a /* nested */ comment */
+    var x = 0x1F + 2.5e-3 - 1_000; // trailing
+}
+while (x <= 10) { x = x + r"raw\n" + "é"; }
+"#;
+
+const FRAGMENTS: &[&str] = &[
+    "", " ", "\n", "\"", "${", "}", "{", "/*", "*/", "//", "1", ".", "e", "+", "_", "x", "é",
+    "r", "\\", "$", "0x", "/", "*", "!=", "\"s ${t}\"",
+];
+
+#[test]
+fn randomized_edits_match_a_full_rescan() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..3000 {
+        let mut source = String::from(PROGRAM);
+        // Apply a few edits in a row, each re-lexed against the last result
+        for _ in 0..3 {
+            let boundaries: Vec<usize> = (0..=source.len())
+                .filter(|&offset| source.is_char_boundary(offset))
+                .collect();
+            let mut start = boundaries[random.below(boundaries.len())];
+            let mut end = boundaries[random.below(boundaries.len())];
+            if start > end {
+                std::mem::swap(&mut start, &mut end);
+            }
+            if end - start > 12 {
+                end = *boundaries.iter().rev().find(|&&b| b <= start + 12).unwrap();
+            }
+            let replacement = FRAGMENTS[random.below(FRAGMENTS.len())];
+            let edit = TextEdit::new(start..end, replacement);
+            check_edit(&source, edit.clone());
+            source = edit.apply(&source);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Works out, before the program runs, which declaration every use of a
+//! variable refers to. Closures then see the variables that were in scope
+//! where they were written, not whatever is in scope when they are called.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::parser::{
+    ClassStatement, Expr, FunctionStatement, InterpolationPart, Literal, Program, Stmt,
+};
+use crate::span::Span;
+use crate::token::Token;
+
+/// A use or declaration of a variable that is an error even though it
+/// parses. `name` is the variable, and `span` where it is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolveError {
+    pub name: String,
+    pub message: String,
+    pub span: Span,
+}
+
+impl ResolveError {
+    fn new(name: &str, span: Span, message: &str) -> ResolveError {
+        ResolveError {
+            name: name.to_string(),
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    /// The error without its line, `Error at 'a': ...`, for callers that
+    /// say where it is in their own way.
+    pub fn summary(&self) -> String {
+        format!("Error at '{}': {}", self.name, self.message)
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] {}", self.span.line, self.summary())
+    }
+}
+
+struct Resolver {
+    // The local scopes around the code being resolved, innermost last. Each
+    // maps its variables to whether their initializer has finished. Globals
+    // are not tracked, they are looked up by name when the program runs.
+    scopes: Vec<HashMap<String, bool>>,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    fn new() -> Resolver {
+        Resolver {
+            scopes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.insert(name.lexeme.to_string(), false).is_some() {
+            let error = ResolveError::new(
+                name.lexeme,
+                name.span,
+                "Already a variable with this name in this scope.",
+            );
+            self.errors.push(error);
+        }
+    }
+
+    fn define(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    // How many scopes out `name` is declared, `None` when it is global
+    fn depth_of(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name))
+    }
+
+    fn resolve_statements(&mut self, statements: &mut [Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Expression(stmt) => self.resolve_expression(&mut stmt.expression),
+            Stmt::Print(stmt) => self.resolve_expression(&mut stmt.expression),
+            Stmt::Var(stmt) => {
+                // Declared first so that reading it in the initializer is
+                // caught rather than finding an outer variable of that name
+                self.declare(&stmt.name);
+                if let Some(initializer) = &mut stmt.initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.define(stmt.name.lexeme);
+            }
+            Stmt::Block(stmt) => {
+                self.begin_scope();
+                self.resolve_statements(&mut stmt.statements);
+                self.end_scope();
+            }
+            Stmt::If(stmt) => {
+                self.resolve_expression(&mut stmt.condition);
+                self.resolve_statement(&mut stmt.then_branch);
+                if let Some(else_branch) = &mut stmt.else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::While(stmt) => {
+                self.resolve_expression(&mut stmt.condition);
+                self.resolve_statement(&mut stmt.body);
+            }
+            Stmt::Function(function) => {
+                // Defined before its body so that it can call itself
+                self.declare(&function.name);
+                self.define(function.name.lexeme);
+                self.resolve_function(function);
+            }
+            Stmt::Return(stmt) => {
+                if let Some(value) = &mut stmt.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Class(stmt) => self.resolve_class(stmt),
+        }
+    }
+
+    // The scopes mirror the environments the interpreter creates: one
+    // holding `super` for a subclass, and one holding `this` for each bound
+    // method.
+    fn resolve_class(&mut self, stmt: &mut ClassStatement) {
+        self.declare(&stmt.name);
+        self.define(stmt.name.lexeme);
+        if let Some(superclass) = &mut stmt.superclass {
+            self.resolve_expression(superclass);
+            self.begin_scope();
+            self.define("super");
+        }
+        self.begin_scope();
+        self.define("this");
+        for method in &mut stmt.methods {
+            self.resolve_function(method);
+        }
+        self.end_scope();
+        if stmt.superclass.is_some() {
+            self.end_scope();
+        }
+    }
+
+    // The parameters and the body share one scope, as they share one
+    // environment when the function is called
+    fn resolve_function(&mut self, function: &mut Rc<FunctionStatement>) {
+        // Only the tree being resolved holds the declaration yet, so this
+        // does not copy it
+        let function = Rc::make_mut(function);
+        self.begin_scope();
+        for param in &function.params {
+            self.declare(param);
+            self.define(param.lexeme);
+        }
+        self.resolve_statements(&mut function.body);
+        self.end_scope();
+    }
+
+    fn resolve_expression(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Literal(literal) => {
+                if let Literal::Variable(name) = &literal.value {
+                    self.check_initialized(name, literal.span);
+                    literal.depth = self.depth_of(name);
+                }
+            }
+            Expr::Assign(assign) => {
+                self.resolve_expression(&mut assign.value);
+                assign.depth = self.depth_of(&assign.name);
+            }
+            Expr::Unary(unary) => self.resolve_expression(&mut unary.right),
+            Expr::Binary(binary) => {
+                self.resolve_expression(&mut binary.left);
+                self.resolve_expression(&mut binary.right);
+            }
+            Expr::Logical(logical) => {
+                self.resolve_expression(&mut logical.left);
+                self.resolve_expression(&mut logical.right);
+            }
+            Expr::Grouping(grouping) => self.resolve_expression(&mut grouping.expression),
+            Expr::Call(call) => {
+                self.resolve_expression(&mut call.callee);
+                for argument in &mut call.arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            Expr::Get(get) => self.resolve_expression(&mut get.object),
+            Expr::Set(set) => {
+                self.resolve_expression(&mut set.object);
+                self.resolve_expression(&mut set.value);
+            }
+            Expr::This(this) => this.depth = self.depth_of("this"),
+            Expr::Super(expr) => expr.depth = self.depth_of("super"),
+            Expr::Interpolation(interpolation) => {
+                for part in &mut interpolation.parts {
+                    if let InterpolationPart::Expr(expr) = part {
+                        self.resolve_expression(expr);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_initialized(&mut self, name: &str, span: Span) {
+        if self.scopes.last().and_then(|scope| scope.get(name)) == Some(&false) {
+            self.errors.push(ResolveError::new(
+                name,
+                span,
+                "Can't read local variable in its own initializer.",
+            ));
+        }
+    }
+}
+
+/// Records in `program` the scope each local variable it uses is declared
+/// in, as the interpreter needs. Fails with the uses and declarations that
+/// are errors even though they parse, like `var a = a;` in a block. Must
+/// be run on a program before it is interpreted.
+pub fn resolve(program: &mut Program) -> Result<(), Vec<ResolveError>> {
+    let mut resolver = Resolver::new();
+    resolver.resolve_statements(&mut program.statements);
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::parser::{parse, Expr, Literal, Stmt};
+use crate::resolver::{resolve, ResolveError};
+use crate::scanner::scan_program;
+
+fn resolve_errors(source: &str) -> Vec<String> {
+    let (tokens, _) = scan_program(source);
+    let mut program = parse(&tokens).unwrap();
+    let errors: Vec<ResolveError> = resolve(&mut program).unwrap_err();
+    errors.iter().map(|error| error.to_string()).collect()
+}
+
+// The depth of the variable that `print` prints in the innermost block of
+// each statement
+fn printed_depths(source: &str) -> Vec<Option<usize>> {
+    fn find(stmt: &Stmt, depths: &mut Vec<Option<usize>>) {
+        match stmt {
+            Stmt::Print(print) => match &print.expression {
+                Expr::Literal(literal) if matches!(literal.value, Literal::Variable(_)) => {
+                    depths.push(literal.depth)
+                }
+                expr => panic!("{} is not a variable", expr),
+            },
+            Stmt::Block(block) => block.statements.iter().for_each(|stmt| find(stmt, depths)),
+            Stmt::Function(function) => function.body.iter().for_each(|stmt| find(stmt, depths)),
+            _ => (),
+        }
+    }
+    let (tokens, _) = scan_program(source);
+    let mut program = parse(&tokens).unwrap();
+    resolve(&mut program).unwrap();
+    let mut depths = Vec::new();
+    program
+        .statements
+        .iter()
+        .for_each(|stmt| find(stmt, &mut depths));
+    depths
+}
+
+#[test]
+fn locals_resolve_to_the_scope_declaring_them() {
+    assert_eq!(
+        [None, Some(0), Some(1), Some(0), None],
+        &printed_depths(
+            "var a; print a; { var b; print b; { print b; var c; print c; print a; } }"
+        )[..]
+    );
+}
+
+#[test]
+fn parameters_are_in_the_function_scope() {
+    assert_eq!(
+        [Some(0), Some(1)],
+        &printed_depths("fun f(x) { print x; { print x; } }")[..]
+    );
+}
+
+#[test]
+fn reading_a_local_in_its_own_initializer_is_an_error() {
+    assert_eq!(
+        ["[line 1] Error at 'a': Can't read local variable in its own initializer."],
+        &resolve_errors("var a = 1; { var a = a + 1; }")[..]
+    );
+}
+
+#[test]
+fn locals_cannot_be_declared_twice_in_one_scope() {
+    assert_eq!(
+        [
+            "[line 1] Error at 'a': Already a variable with this name in this scope.",
+            "[line 2] Error at 'x': Already a variable with this name in this scope.",
+        ],
+        &resolve_errors("{ var a; var a; }\nfun f(x, x) {}")[..]
+    );
+    // Globals can be redefined, and inner scopes can shadow
+    let (tokens, _) = scan_program("var a; var a; { var a; { var a; } }");
+    let mut program = parse(&tokens).unwrap();
+    assert!(resolve(&mut program).is_ok());
+}
+
+#[test]
+fn parsing_does_not_resolve() {
+    let (tokens, _) = scan_program("{ var a; var a; }");
+    let program = parse(&tokens).unwrap();
+    let Stmt::Block(block) = &program.statements[0] else {
+        panic!("{} is not a block", program.statements[0]);
+    };
+    assert_eq!(2, block.statements.len());
+}
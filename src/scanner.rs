@@ -1,167 +1,623 @@
-use crate::token::Token;
-use crate::token::TokenType;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
 
-struct Scanner {
-    code: Vec<char>,
-    tokens: Vec<Token>,
+use unicode_xid::UnicodeXID;
+
+use crate::span::{FileId, Span};
+use crate::token::{Token, TokenType, Trivia, TriviaKind};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScanErrorKind {
+    UnexpectedCharacter,
+    UnterminatedString,
+    InvalidEscape,
+    MalformedNumber,
+    NumberOutOfRange,
+    UnterminatedComment,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub span: Span,
+}
+
+impl ScanError {
+    pub fn new(kind: ScanErrorKind, span: Span, text: String) -> ScanError {
+        ScanError {
+            kind,
+            line: span.line,
+            column: span.column,
+            text,
+            span,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self.kind {
+            ScanErrorKind::UnexpectedCharacter => format!("Unexpected character '{}'.", self.text),
+            ScanErrorKind::UnterminatedString => String::from("Unterminated string."),
+            ScanErrorKind::InvalidEscape => format!("Invalid escape sequence '{}'.", self.text),
+            ScanErrorKind::MalformedNumber => format!("Malformed number '{}'.", self.text),
+            ScanErrorKind::NumberOutOfRange => format!("Number '{}' is out of range.", self.text),
+            ScanErrorKind::UnterminatedComment => String::from("Unterminated block comment."),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}:{}] Error: {}", self.line, self.column, self.message())
+    }
+}
+
+/// Anything the scanner can find in the source. Trivia is what the iterator
+/// skips: whitespace, newlines, comments and text that failed to scan.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Piece<'a> {
+    Token(Token<'a>),
+    Trivia(Trivia<'a>),
+    Error(ScanError),
+}
+
+/// Walks the source text and yields its tokens one at a time. Tokens borrow
+/// their lexemes from the source, so scanning does not copy the program.
+pub struct Scanner<'a> {
+    source: &'a str,
+    file: FileId,
+    // Byte offset of the next character
     current: usize,
-    current_char: char,
     line: usize,
+    column: usize,
+    // Where the token being scanned starts
+    start: usize,
+    start_line: usize,
+    start_column: usize,
+    // A single token can produce several errors (e.g. two bad escapes in a
+    // string); they wait here until the iterator hands them out.
+    queued: VecDeque<Piece<'a>>,
+    // One entry per string interpolation we are inside of, counting the
+    // braces opened within it, so we know which `}` resumes the string.
+    interpolations: Vec<usize>,
+    emitted_eof: bool,
 }
 
-impl Scanner {
-    fn new(program: &str) -> Scanner {
+impl<'a> Scanner<'a> {
+    pub fn new(source: &'a str, file: FileId) -> Scanner<'a> {
         Scanner {
-            code: program.chars().collect::<Vec<char>>(),
-            tokens: Vec::new(),
+            source,
+            file,
             current: 0,
-            current_char: '\0',
             line: 1,
+            column: 0,
+            start: 0,
+            start_line: 1,
+            start_column: 1,
+            queued: VecDeque::new(),
+            interpolations: Vec::new(),
+            emitted_eof: false,
         }
     }
 
+    /// A scanner that starts at byte `offset` of `source`, which sits at
+    /// `line` and `column`. The offset must be between tokens and outside
+    /// of any string interpolation.
+    pub fn resume(
+        source: &'a str,
+        file: FileId,
+        offset: usize,
+        line: usize,
+        column: usize,
+    ) -> Scanner<'a> {
+        let mut scanner = Scanner::new(source, file);
+        scanner.current = offset;
+        scanner.line = line;
+        scanner.column = column - 1;
+        scanner
+    }
+
+    /// Whether the scanner is inside the expression of a string
+    /// interpolation, where the next `}` may resume the string.
+    pub fn in_interpolation(&self) -> bool {
+        !self.interpolations.is_empty()
+    }
+
     fn peek(&self) -> char {
-        if self.current >= self.code.len() {
-            '\0'
+        match self.source.as_bytes().get(self.current) {
+            Some(&byte) if byte.is_ascii() => byte as char,
+            Some(_) => self.source[self.current..].chars().next().unwrap(),
+            None => '\0',
+        }
+    }
+
+    fn peek_nth(&self, n: usize) -> char {
+        self.source[self.current..].chars().nth(n).unwrap_or('\0')
+    }
+
+    fn peek_next(&self) -> char {
+        self.peek_nth(1)
+    }
+
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
         } else {
-            self.code[self.current]
+            self.column += 1;
         }
+        c
     }
 
-    fn next(&mut self) -> char {
-        let nc = self.peek();
-        self.current += 1;
-        self.current_char = nc;
-        nc
+    fn matches(&mut self, expected: char) -> bool {
+        if self.peek() == expected {
+            self.advance();
+            true
+        } else {
+            false
+        }
     }
 
     fn is_at_end(&self) -> bool {
-        self.peek() == '\0'
-    }
-
-    fn add_token(&mut self, token_type: TokenType, lexeme: String) {
-        self.tokens.push(Token::new(token_type, lexeme, self.line));
-    }
-
-    fn scan(&mut self) -> Vec<Token> {
-        while !self.is_at_end() {
-            self.scan_token();
-        }
-        self.tokens.clone()
-    }
-
-    fn scan_token(&mut self) {
-        let c = self.next().to_string();
-        match c.as_str() {
-            "\n" => self.line = self.line + 1,
-            c if c.trim().is_empty() => (),
-            "(" => self.add_token(TokenType::LeftParen, c),
-            ")" => self.add_token(TokenType::RightParen, c),
-            "{" => self.add_token(TokenType::LeftBrace, c),
-            "}" => self.add_token(TokenType::RightBrace, c),
-            "," => self.add_token(TokenType::Comma, c),
-            "." => self.add_token(TokenType::Dot, c),
-            "-" => self.add_token(TokenType::Minus, c),
-            "+" => self.add_token(TokenType::Plus, c),
-            ";" => self.add_token(TokenType::Semicolon, c),
-            "*" => self.add_token(TokenType::Star, c),
-            "!" => {
-                if self.peek() == '=' {
-                    self.next();
-                    self.add_token(TokenType::BangEquals, "!=".to_string());
-                } else {
-                    self.add_token(TokenType::Bang, c);
-                }
+        self.current >= self.source.len()
+    }
+
+    // Source text of the token being scanned
+    fn lexeme(&self) -> &'a str {
+        &self.source[self.start..self.current]
+    }
+
+    fn span(&self) -> Span {
+        Span::new(
+            self.file,
+            self.start,
+            self.current,
+            self.start_line,
+            self.start_column,
+        )
+    }
+
+    fn token(&self, token_type: TokenType) -> Token<'a> {
+        Token::new(token_type, self.lexeme(), self.span())
+    }
+
+    // An error located at the start of the token being scanned
+    fn error(&self, kind: ScanErrorKind, text: String) -> ScanError {
+        ScanError::new(kind, self.span(), text)
+    }
+
+    fn queue_error_at(&mut self, kind: ScanErrorKind, text: String, span: Span) {
+        self.queued
+            .push_back(Piece::Error(ScanError::new(kind, span, text)));
+    }
+
+    fn trivia(&self) -> Trivia<'a> {
+        let text = self.lexeme();
+        let kind = if text == "\n" {
+            TriviaKind::Newline
+        } else if text.starts_with("//") {
+            TriviaKind::LineComment
+        } else if text.starts_with("/*") {
+            TriviaKind::BlockComment
+        } else {
+            TriviaKind::Whitespace
+        };
+        Trivia::new(kind, text, self.span())
+    }
+
+    fn begin_token(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+    }
+
+    /// Scans the next piece of the source, including the trivia that the
+    /// iterator skips. Concatenating the text of every token and trivia
+    /// piece gives back the source. The last piece is the `Eof` token.
+    pub fn next_piece(&mut self) -> Option<Piece<'a>> {
+        if let Some(queued) = self.queued.pop_front() {
+            return Some(queued);
+        }
+        let in_interpolation = self.in_interpolation();
+        if self.is_at_end() {
+            if self.emitted_eof {
+                return None;
+            }
+            self.emitted_eof = true;
+            self.begin_token();
+            let mut eof = self.token(TokenType::Eof);
+            eof.in_interpolation = in_interpolation;
+            return Some(Piece::Token(eof));
+        }
+
+        self.begin_token();
+        let piece = match self.scan_token() {
+            None => Piece::Trivia(self.trivia()),
+            Some(Ok(mut token)) => {
+                token.in_interpolation = in_interpolation;
+                Piece::Token(token)
+            }
+            Some(Err(error)) => {
+                // Keep the text that could not be scanned
+                let skipped = Trivia::new(TriviaKind::Skipped, self.lexeme(), self.span());
+                self.queued.push_back(Piece::Trivia(skipped));
+                Piece::Error(error)
             }
-            "=" => {
-                if self.peek() == '=' {
-                    self.next();
-                    self.add_token(TokenType::EqualsEquals, "==".to_string());
-                } else {
-                    self.add_token(TokenType::Equals, c);
+        };
+        if self.queued.is_empty() {
+            return Some(piece);
+        }
+        self.queued.push_back(piece);
+        self.queued.pop_front()
+    }
+
+    // Scans what comes next in the source. Whitespace and plain comments
+    // produce nothing.
+    fn scan_token(&mut self) -> Option<Result<Token<'a>, ScanError>> {
+        let c = self.advance();
+        let token_type = match c {
+            '\n' => return None,
+            c if c.is_whitespace() => {
+                while self.peek().is_whitespace() && self.peek() != '\n' {
+                    self.advance();
                 }
+                return None;
             }
-            "<" => {
-                if self.peek() == '=' {
-                    self.next();
-                    self.add_token(TokenType::LessEqual, "<=".to_string());
-                } else {
-                    self.add_token(TokenType::Less, c);
+            '(' => TokenType::LeftParen,
+            ')' => TokenType::RightParen,
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
                 }
+                TokenType::LeftBrace
             }
-            ">" => {
-                if self.peek() == '=' {
-                    self.next();
-                    self.add_token(TokenType::GreaterEqual, ">=".to_string());
-                } else {
-                    self.add_token(TokenType::Greater, c);
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    return Some(self.scan_string());
                 }
+                Some(depth) => {
+                    *depth -= 1;
+                    TokenType::RightBrace
+                }
+                None => TokenType::RightBrace,
+            },
+            ',' => TokenType::Comma,
+            '.' => TokenType::Dot,
+            '-' => TokenType::Minus,
+            '+' => TokenType::Plus,
+            ';' => TokenType::Semicolon,
+            '*' => TokenType::Star,
+            '!' if self.matches('=') => TokenType::BangEquals,
+            '!' => TokenType::Bang,
+            '=' if self.matches('=') => TokenType::EqualsEquals,
+            '=' => TokenType::Equals,
+            '<' if self.matches('=') => TokenType::LessEqual,
+            '<' => TokenType::Less,
+            '>' if self.matches('=') => TokenType::GreaterEqual,
+            '>' => TokenType::Greater,
+            '/' if self.matches('/') => return self.scan_line_comment(),
+            '/' if self.matches('*') => return self.scan_block_comment(),
+            '/' => TokenType::Slash,
+            '"' => return Some(self.scan_string()),
+            'r' if self.peek() == '"' => {
+                self.advance();
+                return Some(self.scan_raw_string());
+            }
+            c if is_identifier_start(c) => return Some(Ok(self.scan_identifier())),
+            c if c.is_ascii_digit() => return Some(self.scan_number(c)),
+            c => return Some(Err(self.error(ScanErrorKind::UnexpectedCharacter, c.to_string()))),
+        };
+        Some(Ok(self.token(token_type)))
+    }
+
+    // Comment until the end of the line. Exactly three slashes make it a doc
+    // comment, which is kept as a token for the declaration that follows.
+    fn scan_line_comment(&mut self) -> Option<Result<Token<'a>, ScanError>> {
+        while self.peek() != '\n' && !self.is_at_end() {
+            self.advance();
+        }
+        let lexeme = self.lexeme();
+        if lexeme.starts_with("///") && !lexeme.starts_with("////") {
+            Some(Ok(Token::new_doc_comment(lexeme, self.span())))
+        } else {
+            None
+        }
+    }
+
+    // Block comments nest, so /* a /* b */ c */ is a single comment
+    fn scan_block_comment(&mut self) -> Option<Result<Token<'a>, ScanError>> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                let error = self.error(ScanErrorKind::UnterminatedComment, String::from("/*"));
+                return Some(Err(error));
+            }
+            let c = self.advance();
+            if c == '/' && self.matches('*') {
+                depth += 1;
+            } else if c == '*' && self.matches('/') {
+                depth -= 1;
+            }
+        }
+        None
+    }
+
+    // Strings can span several lines and contain any character. The lexeme
+    // is the text between the quotes, the value has its escapes resolved.
+    //
+    // A `${` inside a string starts an interpolated expression: the text so
+    // far becomes an Interpolation token, the expression is scanned as usual
+    // and its closing `}` brings us back here for the rest of the string. So
+    // "a ${b} c" is scanned as Interpolation(a ), Identifier(b), String( c).
+    fn scan_string(&mut self) -> Result<Token<'a>, ScanError> {
+        let mut value = String::new();
+        let mut escaped = false;
+        let token_type = loop {
+            if self.is_at_end() {
+                return Err(self.unterminated_string_error());
             }
-            "/" => {
-                if self.peek() == '/' {
-                    // Comment until the end of the line
-                    while self.peek() != '\n' && !self.is_at_end() {
-                        self.next();
-                    }
-                } else {
-                    self.add_token(TokenType::Slash, c);
+            let c = self.advance();
+            match c {
+                '"' => break TokenType::String,
+                '$' if self.matches('{') => {
+                    self.interpolations.push(0);
+                    break TokenType::Interpolation;
                 }
+                '\\' => {
+                    escaped = true;
+                    self.scan_escape(&mut value);
+                }
+                c => value.push(c),
             }
-            "\"" => self.scan_string(),
-            c if is_alphabetic(c) => self.scan_identifier(),
-            c if is_numeric(c) => self.scan_number(),
-            _ => panic!("unrecognized char: '{}' at line {}", c, self.line),
         };
+        // Leave out the opening `"` or `}` and the closing `"` or `${`
+        let end = match token_type {
+            TokenType::Interpolation => self.current - 2,
+            _ => self.current - 1,
+        };
+        let lexeme = &self.source[self.start + 1..end];
+        if !escaped {
+            // Nothing was resolved, the value is the lexeme itself
+            value = lexeme.to_string();
+        }
+        let mut token = match token_type {
+            TokenType::Interpolation => Token::new_interpolation(lexeme, value, self.span()),
+            _ => Token::new_string(lexeme, value, self.span()),
+        };
+        token.continues_string = self.source[self.start..].starts_with('}');
+        Ok(token)
     }
 
-    fn scan_string(&mut self) {
-        let st = self.scan_generic(TokenType::String);
-        self.tokens.push(Token::new_string(st, self.line));
+    // Raw strings (r"...") take their contents verbatim, without escapes
+    fn scan_raw_string(&mut self) -> Result<Token<'a>, ScanError> {
+        loop {
+            if self.is_at_end() {
+                return Err(self.unterminated_string_error());
+            }
+            if self.advance() == '"' {
+                break;
+            }
+        }
+        let lexeme = &self.source[self.start + 2..self.current - 1];
+        Ok(Token::new_string(lexeme, lexeme.to_string(), self.span()))
     }
 
-    fn scan_identifier(&mut self) {
-        let id = self.scan_generic(TokenType::Identifier);
-        self.tokens.push(Token::new_identifier(id, self.line));
+    fn unterminated_string_error(&self) -> ScanError {
+        let text = self.lexeme().to_string();
+        self.error(ScanErrorKind::UnterminatedString, text)
     }
 
-    fn scan_number(&mut self) {
-        let lit = self.scan_generic(TokenType::Number);
-        self.tokens
-            .push(Token::new_number(lit.parse::<f64>().unwrap(), self.line));
+    // Called right after a backslash was consumed inside a string
+    fn scan_escape(&mut self, value: &mut String) {
+        let (line, column) = (self.line, self.column);
+        let escape_start = self.current - 1;
+        if self.is_at_end() {
+            return;
+        }
+        let escaped = match self.advance() {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '$' => Some('$'),
+            'u' => self.scan_unicode_escape(),
+            _ => None,
+        };
+        match escaped {
+            Some(escaped) => value.push(escaped),
+            None => {
+                let text = self.source[escape_start..self.current].to_string();
+                let span = Span::new(self.file, escape_start, self.current, line, column);
+                self.queue_error_at(ScanErrorKind::InvalidEscape, text, span);
+            }
+        }
+    }
+
+    // \u{XXXX} with one to six hex digits naming a unicode scalar value
+    fn scan_unicode_escape(&mut self) -> Option<char> {
+        if !self.matches('{') {
+            return None;
+        }
+        let digits_start = self.current;
+        while self.peek().is_ascii_hexdigit() {
+            self.advance();
+        }
+        let digits = &self.source[digits_start..self.current];
+        if !self.matches('}') || digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    // Identifiers follow Unicode's XID_Start/XID_Continue rules, plus `_`
+    fn scan_identifier(&mut self) -> Token<'a> {
+        while is_identifier_char(self.peek()) {
+            self.advance();
+        }
+        Token::new_identifier(self.lexeme(), self.span())
     }
 
-    fn scan_generic(&mut self, ttype: TokenType) -> String {
-        let mut literal = String::from("");
-        if ttype != TokenType::String {
-            // We avoid 1st character if string since it's "
-            literal.push(self.current_char);
+    // Numbers are decimal (1_000, 3.14, 2.5e-3), hexadecimal (0xff) or
+    // binary (0b1010). A `_` is only allowed between two digits.
+    //
+    // A number is out of range when it does not fit in an f64: it is too
+    // big to be finite, or it is written as an integer above 2^53, past
+    // which an f64 cannot hold every integer.
+    fn scan_number(&mut self, first: char) -> Result<Token<'a>, ScanError> {
+        let mut number = match (first, self.peek()) {
+            ('0', 'x' | 'X') => self.scan_radix_number(16),
+            ('0', 'b' | 'B') => self.scan_radix_number(2),
+            _ => self.scan_decimal_number(),
+        };
+
+        // Anything glued to the number makes the whole word malformed
+        while is_identifier_char(self.peek()) {
+            self.advance();
+            number = Err(ScanErrorKind::MalformedNumber);
         }
 
-        while self.current <= self.code.len() {
-            let c = self.peek();
-            if !c.is_ascii_alphanumeric() && !(ttype == TokenType::String && c.is_whitespace()) {
-                if c == '"' && ttype == TokenType::String {
-                    self.next();
+        match number {
+            Ok(number) => Ok(Token::new_number(self.lexeme(), number, self.span())),
+            Err(kind) => Err(self.error(kind, self.lexeme().to_string())),
+        }
+    }
+
+    fn scan_decimal_number(&mut self) -> Result<f64, ScanErrorKind> {
+        self.scan_digits(10);
+        let mut integer = true;
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            integer = false;
+            self.advance();
+            self.scan_digits(10);
+        }
+        if matches!(self.peek(), 'e' | 'E') {
+            let sign = matches!(self.peek_next(), '+' | '-');
+            let digit = if sign { self.peek_nth(2) } else { self.peek_next() };
+            if digit.is_ascii_digit() {
+                integer = false;
+                self.advance();
+                if sign {
+                    self.advance();
                 }
-                break;
+                self.scan_digits(10);
+            }
+        }
+
+        let literal = self.lexeme();
+        if !has_valid_separators(literal, 10) {
+            return Err(ScanErrorKind::MalformedNumber);
+        }
+        let literal = without_separators(literal);
+        if integer {
+            return exact_integer(&literal, 10);
+        }
+        match literal.parse::<f64>() {
+            Ok(number) if number.is_finite() => Ok(number),
+            Ok(_) => Err(ScanErrorKind::NumberOutOfRange),
+            Err(_) => Err(ScanErrorKind::MalformedNumber),
+        }
+    }
+
+    // The leading 0 is already consumed, the radix marker is next
+    fn scan_radix_number(&mut self, radix: u32) -> Result<f64, ScanErrorKind> {
+        self.advance();
+        let digits_start = self.current;
+        self.scan_digits(radix);
+
+        let digits = &self.source[digits_start..self.current];
+        if digits.is_empty() || !has_valid_separators(digits, radix) {
+            return Err(ScanErrorKind::MalformedNumber);
+        }
+        exact_integer(&without_separators(digits), radix)
+    }
+
+    fn scan_digits(&mut self, radix: u32) {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            self.advance();
+        }
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Result<Token<'a>, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_piece()? {
+                Piece::Token(token) => return Some(Ok(token)),
+                Piece::Error(error) => return Some(Err(error)),
+                Piece::Trivia(_) => (),
             }
-            literal.push(c);
-            self.next();
         }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_xid_start() || c == '_'
+}
 
-        literal
+fn is_identifier_char(c: char) -> bool {
+    c.is_xid_continue()
+}
+
+// Every `_` in a number must sit between two digits
+fn has_valid_separators(literal: &str, radix: u32) -> bool {
+    let bytes = literal.as_bytes();
+    bytes.iter().enumerate().all(|(i, byte)| {
+        *byte != b'_'
+            || (i > 0
+                && (bytes[i - 1] as char).is_digit(radix)
+                && i + 1 < bytes.len()
+                && (bytes[i + 1] as char).is_digit(radix))
+    })
+}
+
+// Every integer up to this one has an exact f64
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+// `digits` are all valid digits of `radix`, so parsing can only fail by
+// overflowing
+fn exact_integer(digits: &str, radix: u32) -> Result<f64, ScanErrorKind> {
+    match u64::from_str_radix(digits, radix) {
+        Ok(number) if number <= MAX_EXACT_INTEGER => Ok(number as f64),
+        _ => Err(ScanErrorKind::NumberOutOfRange),
     }
 }
 
-fn is_alphabetic(c: &str) -> bool {
-    c.chars().nth(0).unwrap().is_ascii_alphabetic()
+fn without_separators(literal: &str) -> Cow<'_, str> {
+    if literal.contains('_') {
+        Cow::Owned(literal.replace('_', ""))
+    } else {
+        Cow::Borrowed(literal)
+    }
 }
 
-fn is_numeric(c: &str) -> bool {
-    c.chars().nth(0).unwrap().is_ascii_digit()
+/// Scans the whole program, returning every token it could recognize along
+/// with the errors found on the way. Scanning never stops at the first error
+/// and the tokens always end with an `Eof` token.
+pub fn scan_program(program: &str) -> (Vec<Token<'_>>, Vec<ScanError>) {
+    scan_file(FileId::default(), program)
 }
 
-pub fn scan_program(program: &str) -> Vec<Token> {
-    let mut scanner = Scanner::new(program);
-    scanner.scan()
+/// Like `scan_program`, but every token's span points into `file`.
+pub fn scan_file(file: FileId, program: &str) -> (Vec<Token<'_>>, Vec<ScanError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for scanned in Scanner::new(program, file) {
+        match scanned {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+    (tokens, errors)
 }
@@ -1,125 +1,475 @@
-use crate::scanner::scan_program;
+use crate::scanner::{scan_file, scan_program, ScanErrorKind, Scanner};
+use crate::span::{FileId, Span};
 use crate::token::Token;
 use crate::token::TokenType;
+use crate::token::Value;
 
-fn tokens_to_literals(tokens: &Vec<Token>) -> Vec<String> {
-    tokens.into_iter().map(|token| token.lexeme.clone()).collect()
+fn tokens_to_literals<'a>(tokens: &[Token<'a>]) -> Vec<&'a str> {
+    tokens
+        .iter()
+        .filter(|token| token.token_type != TokenType::Eof)
+        .map(|token| token.lexeme)
+        .collect()
 }
 
 #[test]
-fn empty_string_produces_empty_list_of_tokens() {
-    let tokens = scan_program("");
-    assert_eq!(0, tokens.len());
+fn empty_string_produces_only_eof() {
+    let (tokens, _) = scan_program("");
+    assert_eq!(1, tokens.len());
+    assert_eq!(TokenType::Eof, tokens[0].token_type);
+}
+
+#[test]
+fn eof_is_placed_after_the_last_character() {
+    let (tokens, _) = scan_program("a + b\n  // done");
+    let eof = tokens.last().unwrap();
+    assert_eq!(TokenType::Eof, eof.token_type);
+    assert_eq!("", eof.lexeme);
+    assert_eq!(Span::new(FileId::default(), 15, 15, 2, 10), eof.span);
+    assert_eq!(2, eof.line);
+}
+
+#[test]
+fn eof_comes_after_errors() {
+    let results: Vec<bool> = Scanner::new("a @", FileId::default())
+        .map(|scanned| scanned.is_ok())
+        .collect();
+    assert_eq!([true, false, true], &results[..]);
+}
+
+#[test]
+fn scanner_is_an_iterator_of_results() {
+    let results: Vec<Result<&str, ScanErrorKind>> = Scanner::new("a % \"b\\q\" 1", FileId::default())
+        .map(|scanned| scanned.map(|token| token.lexeme).map_err(|error| error.kind))
+        .collect();
+    assert_eq!(
+        [
+            Ok("a"),
+            Err(ScanErrorKind::UnexpectedCharacter),
+            Err(ScanErrorKind::InvalidEscape),
+            Ok("b\\q"),
+            Ok("1"),
+            Ok(""),
+        ],
+        &results[..]
+    );
+}
+
+#[test]
+fn lexemes_borrow_from_the_source() {
+    let source = String::from("var answer = \"forty two\";");
+    let (tokens, _) = scan_program(&source);
+    let source_range = source.as_bytes().as_ptr_range();
+    for token in &tokens {
+        // The empty Eof lexeme sits right at the end of the source
+        let lexeme = token.lexeme.as_ptr();
+        assert!(source_range.start <= lexeme && lexeme <= source_range.end);
+    }
+    assert_eq!("forty two", tokens[3].lexeme);
 }
 
 #[test]
 fn token_assignment() {
-    let tokens = scan_program("=");
+    let (tokens, _) = scan_program("=");
     assert_eq!(["="], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn token_plus() {
-    let tokens = scan_program("+");
+    let (tokens, _) = scan_program("+");
     assert_eq!(["+"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn token_minus() {
-    let tokens = scan_program("-");
+    let (tokens, _) = scan_program("-");
     assert_eq!(["-"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn token_asterisk() {
-    let tokens = scan_program("*");
+    let (tokens, _) = scan_program("*");
     assert_eq!(["*"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn token_equals() {
-    let tokens = scan_program("==");
+    let (tokens, _) = scan_program("==");
     assert_eq!(["=="], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
-#[should_panic]
 fn invalid_token_should_raise_error() {
-    let tokens = scan_program("=%");
-    assert_eq!(0, tokens.len());
+    let (tokens, errors) = scan_program("=%");
+    assert_eq!(["="], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(1, errors.len());
+    assert_eq!(ScanErrorKind::UnexpectedCharacter, errors[0].kind);
+    assert_eq!(1, errors[0].line);
+    assert_eq!(2, errors[0].column);
+    assert_eq!("%", errors[0].text);
+}
+
+#[test]
+fn scanning_continues_after_errors() {
+    let (tokens, errors) = scan_program("a @ b\n% c");
+    assert_eq!(["a", "b", "c"], &tokens_to_literals(&tokens)[..]);
+    let positions: Vec<(usize, usize, &str)> = errors
+        .iter()
+        .map(|error| (error.line, error.column, error.text.as_str()))
+        .collect();
+    assert_eq!([(1, 3, "@"), (2, 1, "%")], &positions[..]);
 }
 
 #[test]
 fn several_tokens() {
-    let tokens = scan_program(" + - == / ;; *");
+    let (tokens, _) = scan_program(" + - == / ;; *");
     assert_eq!(["+", "-", "==", "/", ";", ";", "*"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn literal() {
-    let tokens = scan_program("banana");
+    let (tokens, _) = scan_program("banana");
     assert_eq!(["banana"], &tokens_to_literals(&tokens)[..]);
 }
 
+#[test]
+fn underscore_identifiers() {
+    let (tokens, errors) = scan_program("my_var _private __x9 _");
+    assert!(errors.is_empty());
+    assert_eq!(["my_var", "_private", "__x9", "_"], &tokens_to_literals(&tokens)[..]);
+    assert!(tokens[..tokens.len() - 1]
+        .iter()
+        .all(|token| token.token_type == TokenType::Identifier));
+}
+
+#[test]
+fn unicode_identifiers() {
+    let (tokens, errors) = scan_program("café = naïve_Ωmega + 変数2;");
+    assert!(errors.is_empty());
+    assert_eq!(["café", "=", "naïve_Ωmega", "+", "変数2", ";"], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(TokenType::Identifier, tokens[2].token_type);
+    assert_eq!((6, 8), (tokens[1].span.start, tokens[2].span.start));
+}
+
+#[test]
+fn identifiers_cannot_start_with_non_xid_characters() {
+    let (tokens, errors) = scan_program("a€b ²x");
+    assert_eq!(["a", "b", "x"], &tokens_to_literals(&tokens)[..]);
+    let texts: Vec<&str> = errors.iter().map(|error| error.text.as_str()).collect();
+    assert_eq!(["€", "²"], &texts[..]);
+}
+
+#[test]
+fn keywords_are_not_prefixes_of_identifiers() {
+    let (tokens, _) = scan_program("or_else classy _if fün");
+    assert!(tokens[..tokens.len() - 1]
+        .iter()
+        .all(|token| token.token_type == TokenType::Identifier));
+}
+
 #[test]
 fn literal_and_token() {
-    let tokens = scan_program("mango=");
+    let (tokens, _) = scan_program("mango=");
     assert_eq!(["mango", "="], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn two_literals() {
-    let tokens = scan_program("t omate");
+    let (tokens, _) = scan_program("t omate");
     assert_eq!(["t", "omate"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn literal_number_and_string() {
-    let tokens = scan_program(r#" banana 1337 "kiwi" "#);
+    let (tokens, _) = scan_program(r#" banana 1337 "kiwi" "#);
     assert_eq!(["banana", "1337", "kiwi"], &tokens_to_literals(&tokens)[..]);
 }
 
+fn number_values(tokens: &[Token]) -> Vec<f64> {
+    tokens
+        .iter()
+        .filter(|token| token.token_type != TokenType::Eof)
+        .map(|token| match token.value {
+            Some(Value::Number(number)) => number,
+            _ => panic!("{:?} is not a number", token),
+        })
+        .collect()
+}
+
+#[test]
+fn decimal_numbers() {
+    let (tokens, errors) = scan_program("2.75 0.5 10 1_000_000 2.5e3 1E-2 6e+1");
+    assert!(errors.is_empty());
+    assert_eq!(
+        ["2.75", "0.5", "10", "1_000_000", "2.5e3", "1E-2", "6e+1"],
+        &tokens_to_literals(&tokens)[..]
+    );
+    assert_eq!(
+        [2.75, 0.5, 10.0, 1_000_000.0, 2500.0, 0.01, 60.0],
+        &number_values(&tokens)[..]
+    );
+}
+
+#[test]
+fn hex_and_binary_numbers() {
+    let (tokens, errors) = scan_program("0xff 0XdeAD_beef 0b1010 0B1111_0000");
+    assert!(errors.is_empty());
+    assert_eq!(
+        [255.0, 3_735_928_559.0, 10.0, 240.0],
+        &number_values(&tokens)[..]
+    );
+    assert_eq!("0XdeAD_beef", tokens[1].lexeme);
+}
+
+#[test]
+fn dot_after_number_without_digits_is_not_a_fraction() {
+    let (tokens, errors) = scan_program("3.abs 4.");
+    assert!(errors.is_empty());
+    assert_eq!(["3", ".", "abs", "4", "."], &tokens_to_literals(&tokens)[..]);
+}
+
+#[test]
+fn malformed_numbers_are_errors() {
+    let (tokens, errors) = scan_program("12abc 0x 0b102 1__0 1_ 2.5_ 1e 7 1_e5");
+    assert_eq!(["7"], &tokens_to_literals(&tokens)[..]);
+    let reported: Vec<(ScanErrorKind, usize, &str)> = errors
+        .iter()
+        .map(|error| (error.kind, error.column, error.text.as_str()))
+        .collect();
+    assert_eq!(
+        [
+            (ScanErrorKind::MalformedNumber, 1, "12abc"),
+            (ScanErrorKind::MalformedNumber, 7, "0x"),
+            (ScanErrorKind::MalformedNumber, 10, "0b102"),
+            (ScanErrorKind::MalformedNumber, 16, "1__0"),
+            (ScanErrorKind::MalformedNumber, 21, "1_"),
+            (ScanErrorKind::MalformedNumber, 24, "2.5_"),
+            (ScanErrorKind::MalformedNumber, 29, "1e"),
+            (ScanErrorKind::MalformedNumber, 34, "1_e5"),
+        ],
+        &reported[..]
+    );
+}
+
+#[test]
+fn numbers_out_of_range_are_errors() {
+    let source = concat!(
+        "9007199254740992 0x20000000000000 1e308 ",
+        "9007199254740993 0x20000000000001 ",
+        "0b100000000000000000000000000000000000000000000000000001 ",
+        "0x1_0000_0000_0000_0000 1e999 1.5e999",
+    );
+    let (tokens, errors) = scan_program(source);
+    assert_eq!(
+        ["9007199254740992", "0x20000000000000", "1e308"],
+        &tokens_to_literals(&tokens)[..]
+    );
+    let reported: Vec<(ScanErrorKind, &str)> = errors
+        .iter()
+        .map(|error| (error.kind, error.text.as_str()))
+        .collect();
+    assert_eq!(
+        [
+            (ScanErrorKind::NumberOutOfRange, "9007199254740993"),
+            (ScanErrorKind::NumberOutOfRange, "0x20000000000001"),
+            (
+                ScanErrorKind::NumberOutOfRange,
+                "0b100000000000000000000000000000000000000000000000000001"
+            ),
+            (ScanErrorKind::NumberOutOfRange, "0x1_0000_0000_0000_0000"),
+            (ScanErrorKind::NumberOutOfRange, "1e999"),
+            (ScanErrorKind::NumberOutOfRange, "1.5e999"),
+        ],
+        &reported[..]
+    );
+    assert_eq!("Number '1e999' is out of range.", errors[4].message());
+}
+
 #[test]
 fn complex_expression() {
-    let tokens = scan_program("radio = pi*3 + 2;");
+    let (tokens, _) = scan_program("radio = pi*3 + 2;");
     assert_eq!(["radio", "=", "pi", "*", "3", "+", "2", ";"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn complex_expression_comparisons() {
-    let tokens = scan_program("4 < 19>2 >= !1 <= 1!=10");
+    let (tokens, _) = scan_program("4 < 19>2 >= !1 <= 1!=10");
     assert_eq!(["4", "<", "19", ">", "2", ">=", "!", "1", "<=", "1", "!=", "10"],
                &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn parentheses_and_braces() {
-    let tokens = scan_program("((2)) == !{{4}}");
+    let (tokens, _) = scan_program("((2)) == !{{4}}");
     assert_eq!(["(", "(", "2", ")", ")", "==", "!", "{", "{", "4", "}", "}"],
                &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn single_letter_as_string() {
-    let tokens = scan_program(r#""f""#);
+    let (tokens, _) = scan_program(r#""f""#);
     assert_eq!(["f"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn string_with_spaces() {
-    let tokens = scan_program(r#""canada is a nice country""#);
+    let (tokens, _) = scan_program(r#""canada is a nice country""#);
     assert_eq!(["canada is a nice country"], &tokens_to_literals(&tokens)[..]);
 }
 
+#[test]
+fn string_with_punctuation_and_unicode() {
+    let (tokens, errors) = scan_program(r#""a, b! ¿qué tal? 日本" x"#);
+    assert!(errors.is_empty());
+    assert_eq!(["a, b! ¿qué tal? 日本", "x"], &tokens_to_literals(&tokens)[..]);
+}
+
+#[test]
+fn multiline_string_keeps_line_count() {
+    let (tokens, errors) = scan_program("\"one\ntwo\nthree\" four");
+    assert!(errors.is_empty());
+    assert_eq!(TokenType::String, tokens[0].token_type);
+    assert_eq!(Some(Value::String(String::from("one\ntwo\nthree"))), tokens[0].value);
+    assert_eq!(1, tokens[0].line);
+    assert_eq!(3, tokens[1].line);
+    assert_eq!(8, tokens[1].span.column);
+}
+
+#[test]
+fn string_escapes() {
+    let (tokens, errors) = scan_program(r#""tab\t nl\n quote\" backslash\\ heart\u{2764}""#);
+    assert!(errors.is_empty());
+    assert_eq!(r#"tab\t nl\n quote\" backslash\\ heart\u{2764}"#, tokens[0].lexeme);
+    assert_eq!(
+        Some(Value::String(String::from("tab\t nl\n quote\" backslash\\ heart\u{2764}"))),
+        tokens[0].value
+    );
+}
+
+#[test]
+fn invalid_escapes_are_reported() {
+    let (tokens, errors) = scan_program(r#""a\qb\u{110000}c""#);
+    assert_eq!(Some(Value::String(String::from("abc"))), tokens[0].value);
+    let reported: Vec<(ScanErrorKind, usize, &str)> = errors
+        .iter()
+        .map(|error| (error.kind, error.column, error.text.as_str()))
+        .collect();
+    assert_eq!(
+        [(ScanErrorKind::InvalidEscape, 3, "\\q"), (ScanErrorKind::InvalidEscape, 6, "\\u{110000}")],
+        &reported[..]
+    );
+}
+
+#[test]
+fn raw_strings_ignore_escapes() {
+    let (tokens, errors) = scan_program(r#"r"C:\new\table" r"#);
+    assert!(errors.is_empty());
+    assert_eq!(Some(Value::String(String::from(r"C:\new\table"))), tokens[0].value);
+    assert_eq!(["C:\\new\\table", "r"], &tokens_to_literals(&tokens)[..]);
+}
+
+fn token_types(tokens: &[Token]) -> Vec<TokenType> {
+    tokens.iter().map(|token| token.token_type).collect()
+}
+
+#[test]
+fn string_interpolation_segments() {
+    let (tokens, errors) = scan_program(r#""Hello ${name}, you are ${age + 1}!""#);
+    assert!(errors.is_empty());
+    assert_eq!(
+        [
+            TokenType::Interpolation,
+            TokenType::Identifier,
+            TokenType::Interpolation,
+            TokenType::Identifier,
+            TokenType::Plus,
+            TokenType::Number,
+            TokenType::String,
+            TokenType::Eof,
+        ],
+        &token_types(&tokens)[..]
+    );
+    assert_eq!(["Hello ", "name", ", you are ", "age", "+", "1", "!"], &tokens_to_literals(&tokens)[..]);
+    assert_eq!((0, 9), (tokens[0].span.start, tokens[0].span.end));
+    assert_eq!((13, 26), (tokens[2].span.start, tokens[2].span.end));
+    assert_eq!((33, 36), (tokens[6].span.start, tokens[6].span.end));
+}
+
+#[test]
+fn tokens_record_where_interpolations_are() {
+    let (tokens, _) = scan_program(r#""a ${b + "c"} d" e"#);
+    let flags: Vec<(&str, bool, bool)> = tokens
+        .iter()
+        .map(|token| (token.lexeme, token.continues_string, token.in_interpolation))
+        .collect();
+    assert_eq!(
+        [
+            ("a ", false, false),
+            ("b", false, true),
+            ("+", false, true),
+            ("c", false, true),
+            (" d", true, true),
+            ("e", false, false),
+            ("", false, false),
+        ],
+        &flags[..]
+    );
+}
+
+#[test]
+fn braces_and_strings_nest_inside_interpolation() {
+    let (tokens, errors) = scan_program(r#""a ${f({x}) + "b ${c}"} d" }"#);
+    assert!(errors.is_empty());
+    assert_eq!(
+        [
+            TokenType::Interpolation,
+            TokenType::Identifier,
+            TokenType::LeftParen,
+            TokenType::LeftBrace,
+            TokenType::Identifier,
+            TokenType::RightBrace,
+            TokenType::RightParen,
+            TokenType::Plus,
+            TokenType::Interpolation,
+            TokenType::Identifier,
+            TokenType::String,
+            TokenType::String,
+            TokenType::RightBrace,
+            TokenType::Eof,
+        ],
+        &token_types(&tokens)[..]
+    );
+    assert_eq!(Some(Value::String(String::from(" d"))), tokens[11].value);
+}
+
+#[test]
+fn dollar_without_brace_and_escaped_interpolation_are_text() {
+    let (tokens, errors) = scan_program(r#""cost: $5 \${not}" r"${raw}""#);
+    assert!(errors.is_empty());
+    assert_eq!([TokenType::String, TokenType::String, TokenType::Eof], &token_types(&tokens)[..]);
+    assert_eq!(Some(Value::String(String::from("cost: $5 ${not}"))), tokens[0].value);
+    assert_eq!(Some(Value::String(String::from("${raw}"))), tokens[1].value);
+}
+
+#[test]
+fn unterminated_string_is_an_error() {
+    let (tokens, errors) = scan_program("x = \"abc\ndef");
+    assert_eq!(["x", "="], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(1, errors.len());
+    assert_eq!(ScanErrorKind::UnterminatedString, errors[0].kind);
+    assert_eq!((1, 5), (errors[0].line, errors[0].column));
+    assert_eq!("\"abc\ndef", errors[0].text);
+}
+
 #[test]
 fn newlines() {
-    let tokens = scan_program("3*\n2 + \n 3;");
+    let (tokens, _) = scan_program("3*\n2 + \n 3;");
     assert_eq!(["3", "*", "2", "+", "3", ";"], &tokens_to_literals(&tokens)[..]);
 }
 
 #[test]
 fn newlines_token_saves_lines() {
-    let tokens = scan_program("4\n3\n2 + 3");
+    let (tokens, _) = scan_program("4\n3\n2 + 3");
     assert_eq!(1, tokens[0].line);
     assert_eq!(2, tokens[1].line);
     assert_eq!(3, tokens[2].line);
@@ -127,15 +477,70 @@ fn newlines_token_saves_lines() {
     assert_eq!(3, tokens[4].line);
 }
 
+#[test]
+fn tokens_carry_spans() {
+    let (tokens, _) = scan_program("var x >=\n  \"hello\"; // é\ny");
+    let spans: Vec<(usize, usize, usize, usize)> = tokens
+        .iter()
+        .map(|token| (token.span.start, token.span.end, token.span.line, token.span.column))
+        .collect();
+    assert_eq!(
+        [(0, 3, 1, 1), (4, 5, 1, 5), (6, 8, 1, 7), (11, 18, 2, 3), (18, 19, 2, 10), (26, 27, 3, 1), (27, 27, 3, 2)],
+        &spans[..]
+    );
+}
+
+#[test]
+fn tokens_carry_their_file() {
+    let (tokens, _) = scan_file(FileId(3), "a");
+    assert_eq!(Span::new(FileId(3), 0, 1, 1, 1), tokens[0].span);
+}
+
 #[test]
 fn comments() {
-    let tokens = scan_program("4 // this is a comment \n 5;");
+    let (tokens, _) = scan_program("4 // this is a comment \n 5;");
     assert_eq!(["4", "5", ";"], &tokens_to_literals(&tokens)[..]);
 }
 
+#[test]
+fn block_comments() {
+    let (tokens, errors) = scan_program("1 /* one\ntwo */ 2 /**/ 3");
+    assert!(errors.is_empty());
+    assert_eq!(["1", "2", "3"], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(2, tokens[1].line);
+}
+
+#[test]
+fn block_comments_nest() {
+    let (tokens, errors) = scan_program("a /* outer /* inner */ still\n comment */ b");
+    assert!(errors.is_empty());
+    assert_eq!(["a", "b"], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(2, tokens[1].line);
+}
+
+#[test]
+fn unterminated_block_comment_is_an_error() {
+    let (tokens, errors) = scan_program("a\n /* outer /* inner */ b");
+    assert_eq!(["a"], &tokens_to_literals(&tokens)[..]);
+    assert_eq!(1, errors.len());
+    assert_eq!(ScanErrorKind::UnterminatedComment, errors[0].kind);
+    assert_eq!((2, 2), (errors[0].line, errors[0].column));
+}
+
+#[test]
+fn doc_comments_are_tokens() {
+    let (tokens, errors) = scan_program("// plain\n/// Says hi.\n////not docs\nfun");
+    assert!(errors.is_empty());
+    let token_types: Vec<TokenType> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!([TokenType::DocComment, TokenType::Fun, TokenType::Eof], &token_types[..]);
+    assert_eq!("/// Says hi.", tokens[0].lexeme);
+    assert_eq!(Some(Value::String(String::from("Says hi."))), tokens[0].value);
+    assert_eq!(2, tokens[0].line);
+}
+
 #[test]
 fn keywords_1() {
-    let tokens: Vec<Token> = scan_program("and class else false fun for if nil or print");
+    let (tokens, _) = scan_program("and class else false fun for if nil or print");
     assert_eq!(["and", "class", "else", "false", "fun", "for", "if", "nil", "or", "print"],
                &tokens_to_literals(&tokens)[..]
     );
@@ -151,6 +556,7 @@ fn keywords_1() {
             TokenType::Nil,
             TokenType::Or,
             TokenType::Print,
+            TokenType::Eof,
         ],
         &token_types[..]
     );
@@ -158,7 +564,7 @@ fn keywords_1() {
 
 #[test]
 fn keywords_2() {
-    let tokens: Vec<Token> = scan_program("return super this true var while");
+    let (tokens, _) = scan_program("return super this true var while");
     assert_eq!(["return", "super", "this", "true", "var", "while"],
                &tokens_to_literals(&tokens)[..]
     );
@@ -170,6 +576,7 @@ fn keywords_2() {
             TokenType::True,
             TokenType::Var,
             TokenType::While,
+            TokenType::Eof,
         ],
         &token_types[..]
     );
@@ -178,7 +585,7 @@ fn keywords_2() {
 // TODO this changes now with Lox
 #[test]
 fn sample_program() {
-    let tokens = scan_program(
+    let (tokens, _) = scan_program(
         r#"
             fn square(x) {
                 return x * x;
@@ -0,0 +1,107 @@
+//! The registry of loaded source files. Every file gets a `FileId` that the
+//! spans of its tokens, expressions and errors carry, so a diagnostic can
+//! name the file it comes from when several are loaded.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::scanner::{scan_file, ScanError};
+use crate::span::{FileId, Span};
+use crate::token::Token;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    // Byte offset where each line starts, the first one always 0
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: String, source: String) -> SourceFile {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        SourceFile {
+            name,
+            source,
+            line_starts,
+        }
+    }
+
+    /// The 1-based line and column of a byte offset. Columns count
+    /// characters, not bytes.
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..offset].chars().count() + 1;
+        (line + 1, column)
+    }
+
+    /// The text of a 1-based line, without its line break.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        Some(self.source[start..end].trim_end_matches('\r'))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap { files: Vec::new() }
+    }
+
+    /// Registers a file and returns the id its spans will carry.
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(SourceFile::new(name.into(), source.into()));
+        FileId(self.files.len() - 1)
+    }
+
+    /// Reads a file from disk and registers it under its path.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<FileId> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        Ok(self.add(path.display().to_string(), source))
+    }
+
+    /// Panics if `file` was not handed out by this map.
+    pub fn file(&self, file: FileId) -> &SourceFile {
+        &self.files[file.0]
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.file(file).name
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        &self.file(file).source
+    }
+
+    pub fn location(&self, file: FileId, offset: usize) -> (usize, usize) {
+        self.file(file).location(offset)
+    }
+
+    /// Where a span starts, as `lib/util.lox:12:5`.
+    pub fn describe(&self, span: Span) -> String {
+        let (line, column) = self.location(span.file, span.start);
+        format!("{}:{}:{}", self.name(span.file), line, column)
+    }
+
+    /// Scans a registered file. Its tokens borrow from the map.
+    pub fn scan(&self, file: FileId) -> (Vec<Token<'_>>, Vec<ScanError>) {
+        scan_file(file, self.source(file))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::scanner::ScanErrorKind;
+use crate::source_map::SourceMap;
+use crate::span::FileId;
+use crate::token::TokenType;
+
+#[test]
+fn files_get_their_own_ids() {
+    let mut map = SourceMap::new();
+    let main = map.add("main.lox", "print 1;");
+    let util = map.add("lib/util.lox", "print 2;");
+    assert_eq!(FileId(0), main);
+    assert_eq!(FileId(1), util);
+    assert_eq!("main.lox", map.name(main));
+    assert_eq!("print 2;", map.source(util));
+}
+
+#[test]
+fn offsets_map_to_lines_and_columns() {
+    let mut map = SourceMap::new();
+    let file = map.add("a.lox", "ab\ncafé x\n\nz");
+    assert_eq!((1, 1), map.location(file, 0));
+    assert_eq!((1, 3), map.location(file, 2));
+    assert_eq!((2, 1), map.location(file, 3));
+    // é takes two bytes but one column
+    assert_eq!((2, 6), map.location(file, 9));
+    assert_eq!((3, 1), map.location(file, 11));
+    assert_eq!((4, 1), map.location(file, 12));
+    assert_eq!((4, 2), map.location(file, 13));
+}
+
+#[test]
+fn lines_are_returned_without_breaks() {
+    let mut map = SourceMap::new();
+    let file = map.add("a.lox", "one\r\ntwo\n");
+    let source_file = map.file(file);
+    assert_eq!(Some("one"), source_file.line(1));
+    assert_eq!(Some("two"), source_file.line(2));
+    assert_eq!(Some(""), source_file.line(3));
+    assert_eq!(None, source_file.line(4));
+    assert_eq!(None, source_file.line(0));
+}
+
+#[test]
+fn spans_are_described_with_their_file() {
+    let mut map = SourceMap::new();
+    map.add("main.lox", "print 1;");
+    let util = map.add("lib/util.lox", "\n\n  var x = 1;");
+    let (tokens, errors) = map.scan(util);
+    assert!(errors.is_empty());
+    let x = tokens
+        .iter()
+        .find(|token| token.token_type == TokenType::Identifier)
+        .unwrap();
+    assert_eq!(util, x.span.file);
+    assert_eq!("lib/util.lox:3:7", map.describe(x.span));
+    // The scanner and the map agree on positions
+    assert_eq!((x.span.line, x.span.column), map.location(util, x.span.start));
+}
+
+#[test]
+fn scan_errors_carry_their_file() {
+    let mut map = SourceMap::new();
+    map.add("main.lox", "");
+    let util = map.add("lib/util.lox", "a # b \"c\\q\"");
+    let (_, errors) = map.scan(util);
+    let described: Vec<(ScanErrorKind, String)> = errors
+        .iter()
+        .map(|error| (error.kind, map.describe(error.span)))
+        .collect();
+    assert_eq!(
+        [
+            (ScanErrorKind::UnexpectedCharacter, String::from("lib/util.lox:1:3")),
+            (ScanErrorKind::InvalidEscape, String::from("lib/util.lox:1:9")),
+        ],
+        &described[..]
+    );
+}
@@ -0,0 +1,34 @@
+/// Identifies the source file a span belongs to.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct FileId(pub usize);
+
+/// A range of source code. `start` and `end` are byte offsets into the file,
+/// `line` and `column` locate `start` for humans (both 1-based).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            file,
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns a span that starts where `self` starts and ends where `other` ends.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 
-#[derive(PartialEq, Clone)]
+use crate::span::Span;
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     String(String),
     Number(f64),
@@ -33,6 +35,9 @@ pub enum TokenType {
     Identifier,
     String,
     Number,
+    // The part of a string literal before an interpolated `${expression}`
+    Interpolation,
+    DocComment,
     // Keywords
     And,
     Class,
@@ -50,52 +55,123 @@ pub enum TokenType {
     True,
     Var,
     While,
+    // Always the last token of a scanned program
+    Eof,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+    // Source text that could not be scanned into a token
+    Skipped,
 }
 
+/// Source text between tokens that does not affect the meaning of the
+/// program, kept for tools that have to reproduce the source exactly.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl<'a> Trivia<'a> {
+    pub fn new(kind: TriviaKind, text: &'a str, span: Span) -> Trivia<'a> {
+        Trivia { kind, text, span }
+    }
+}
+
+/// A token borrows its lexeme from the source it was scanned from.
 #[derive(PartialEq, Clone)]
-pub struct Token {
+pub struct Token<'a> {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: &'a str,
     pub value: Option<Value>,
     pub line: usize,
+    pub span: Span,
+    /// For String and Interpolation tokens, whether the segment continues a
+    /// string after the `}` of an interpolated expression.
+    pub continues_string: bool,
+    /// Whether the scanner was inside a string interpolation when it
+    /// started on this token.
+    pub in_interpolation: bool,
 }
 
-impl fmt::Debug for Token {
+impl fmt::Debug for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}[{}]", self.token_type, self.lexeme)
     }
 }
 
-impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Token {
+impl<'a> Token<'a> {
+    pub fn new(token_type: TokenType, lexeme: &'a str, span: Span) -> Token<'a> {
         Token {
             token_type,
             lexeme,
             value: None,
-            line,
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
         }
     }
 
-    pub fn new_string(string: String, line: usize) -> Token {
+    pub fn new_string(lexeme: &'a str, string: String, span: Span) -> Token<'a> {
         Token {
             token_type: TokenType::String,
-            lexeme: string.clone(),
-            value: Some(Value::String(string.clone())),
-            line,
+            lexeme,
+            value: Some(Value::String(string)),
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
         }
     }
 
-    pub fn new_number(number: f64, line: usize) -> Token {
+    pub fn new_number(lexeme: &'a str, number: f64, span: Span) -> Token<'a> {
         Token {
             token_type: TokenType::Number,
-            lexeme: number.to_string(),
+            lexeme,
             value: Some(Value::Number(number)),
-            line,
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
+        }
+    }
+
+    pub fn new_interpolation(lexeme: &'a str, string: String, span: Span) -> Token<'a> {
+        Token {
+            token_type: TokenType::Interpolation,
+            lexeme,
+            value: Some(Value::String(string)),
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
+        }
+    }
+
+    /// The value of a doc comment is its text without the leading `///`.
+    pub fn new_doc_comment(lexeme: &'a str, span: Span) -> Token<'a> {
+        let text = lexeme.trim_start_matches('/');
+        let text = text.strip_prefix(' ').unwrap_or(text).to_string();
+        Token {
+            token_type: TokenType::DocComment,
+            lexeme,
+            value: Some(Value::String(text)),
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
         }
     }
 
-    pub fn new_identifier(identifier: String, line: usize) -> Token {
-        let token_type = match identifier.as_str() {
+    pub fn new_identifier(identifier: &'a str, span: Span) -> Token<'a> {
+        let token_type = match identifier {
             "and" => TokenType::And,
             "class" => TokenType::Class,
             "else" => TokenType::Else,
@@ -118,7 +194,10 @@ impl Token {
             token_type,
             lexeme: identifier,
             value: None,
-            line,
+            line: span.line,
+            span,
+            continues_string: false,
+            in_interpolation: false,
         }
     }
 }
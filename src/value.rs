@@ -0,0 +1,208 @@
+//! Values a running Lox program works with. Unlike `token::Value`, which is
+//! only what a literal in the source can spell, these include booleans, nil,
+//! functions, classes and their instances.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::parser::FunctionStatement;
+
+#[derive(Clone)]
+pub enum Value<'a> {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Rc<str>),
+    Function(Rc<Function<'a>>),
+    Class(Rc<Class<'a>>),
+    Instance(Rc<RefCell<Instance<'a>>>),
+}
+
+impl Value<'_> {
+    /// `nil` and `false` are false, everything else is true.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Boolean(false))
+    }
+}
+
+/// Functions, classes and instances are only equal to themselves.
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Function(left), Value::Function(right)) => Rc::ptr_eq(left, right),
+            (Value::Class(left), Value::Class(right)) => Rc::ptr_eq(left, right),
+            (Value::Instance(left), Value::Instance(right)) => Rc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
+}
+
+/// Prints values the way Lox's `print` does.
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Nil => f.write_str("nil"),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            // Lox drops the `.0` of whole numbers, `3` rather than `3.0`
+            Value::Number(number) => {
+                let number = format_number(*number);
+                f.write_str(number.strip_suffix(".0").unwrap_or(&number))
+            }
+            Value::String(string) => f.write_str(string),
+            Value::Function(function) => function.fmt(f),
+            Value::Class(class) => f.write_str(class.name),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+        }
+    }
+}
+
+/// Formats a number as Java's `Double.toString` does, which is what the
+/// reference implementation prints: `3.0`, `0.5`, and very large or small
+/// numbers in scientific notation, `1.0E21`.
+pub fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        return String::from("NaN");
+    }
+    if number.is_infinite() {
+        return String::from(if number > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+    let magnitude = number.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        let decimal = number.to_string();
+        if decimal.contains('.') {
+            return decimal;
+        }
+        return decimal + ".0";
+    }
+    let scientific = format!("{:e}", number);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    if mantissa.contains('.') {
+        format!("{}E{}", mantissa, exponent)
+    } else {
+        format!("{}.0E{}", mantissa, exponent)
+    }
+}
+
+impl fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "{:?}", string),
+            value => fmt::Display::fmt(value, f),
+        }
+    }
+}
+
+/// A function declared in Lox, together with the environment it was
+/// declared in, which its body can keep using after that scope ended.
+pub struct Function<'a> {
+    pub declaration: Rc<FunctionStatement<'a>>,
+    pub closure: Rc<RefCell<Environment<'a>>>,
+    // Whether this is a class's `init` method, which always returns `this`
+    pub is_initializer: bool,
+}
+
+impl<'a> Function<'a> {
+    pub fn new(
+        declaration: Rc<FunctionStatement<'a>>,
+        closure: Rc<RefCell<Environment<'a>>>,
+        is_initializer: bool,
+    ) -> Function<'a> {
+        Function {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    /// The method bound to `instance`: a copy whose closure has `this`.
+    pub fn bind(&self, instance: Rc<RefCell<Instance<'a>>>) -> Function<'a> {
+        let mut environment = Environment::with_enclosing(self.closure.clone());
+        environment.define("this", Value::Instance(instance));
+        Function::new(
+            self.declaration.clone(),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.declaration.name.lexeme
+    }
+
+    pub fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+}
+
+impl fmt::Display for Function<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.name())
+    }
+}
+
+pub struct Class<'a> {
+    pub name: &'a str,
+    pub superclass: Option<Rc<Class<'a>>>,
+    pub methods: HashMap<&'a str, Rc<Function<'a>>>,
+}
+
+impl<'a> Class<'a> {
+    pub fn new(
+        name: &'a str,
+        superclass: Option<Rc<Class<'a>>>,
+        methods: HashMap<&'a str, Rc<Function<'a>>>,
+    ) -> Class<'a> {
+        Class {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    /// Looks for a method in the class and then up its superclasses.
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function<'a>>> {
+        match self.methods.get(name) {
+            Some(method) => Some(method.clone()),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+
+    /// Calling a class takes the arguments of its initializer.
+    pub fn arity(&self) -> usize {
+        self.find_method("init").map_or(0, |init| init.arity())
+    }
+}
+
+pub struct Instance<'a> {
+    pub class: Rc<Class<'a>>,
+    fields: HashMap<String, Value<'a>>,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(class: Rc<Class<'a>>) -> Instance<'a> {
+        Instance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Fields shadow methods of the same name.
+    pub fn get(instance: &Rc<RefCell<Instance<'a>>>, name: &str) -> Option<Value<'a>> {
+        if let Some(value) = instance.borrow().fields.get(name) {
+            return Some(value.clone());
+        }
+        let method = instance.borrow().class.find_method(name)?;
+        Some(Value::Function(Rc::new(method.bind(instance.clone()))))
+    }
+
+    pub fn set(&mut self, name: &str, value: Value<'a>) {
+        self.fields.insert(name.to_string(), value);
+    }
+}